@@ -0,0 +1,803 @@
+// Ciclo de vida do bot: contexto compartilhado, filas por chat, polling e encerramento
+use frankenstein::client_reqwest::Bot;
+use frankenstein::methods::{GetChatParams, GetUpdatesParams};
+use frankenstein::types::{CallbackQuery, Message};
+use frankenstein::updates::UpdateContent;
+use frankenstein::AsyncTelegramApi;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::{sleep, Duration};
+use tokio_util::task::TaskTracker;
+use tracing::{error, info, warn, Instrument};
+
+use crate::config::Config;
+use crate::conversa::{self, Sessao, UserState};
+use crate::handlers::{callback_span, process_callback, process_message, set_menu_commands, update_span};
+use crate::metrics::{self, Metrics};
+use crate::outbox::Outbox;
+use crate::replay::UpdateRecorder;
+use crate::storage::Storage;
+
+// Sessão de um único usuário, com trava própria
+pub(crate) type UserSlot = Arc<Mutex<Option<Sessao>>>;
+
+// Mapa para rastrear o estado de cada usuário. O mapa só é travado para localizar a entrada;
+// cada usuário tem sua própria trava, e nenhuma delas é mantida durante I/O de rede ou de arquivo.
+// Só quem abre uma conversa ganha entrada, e ela é descartada quando a conversa termina
+#[derive(Default)]
+pub struct UserStates {
+    slots: std::sync::Mutex<HashMap<i64, UserSlot>>,
+}
+
+impl UserStates {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<i64, UserSlot>> {
+        self.slots.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Entrada do usuário, criada se ainda não existir; só para quem vai gravar uma conversa
+    fn slot(&self, user_id: i64) -> UserSlot {
+        self.lock().entry(user_id).or_default().clone()
+    }
+
+    // Entrada do usuário, sem criá-la
+    pub(crate) fn get(&self, user_id: i64) -> Option<UserSlot> {
+        self.lock().get(&user_id).cloned()
+    }
+
+    pub(crate) fn slots(&self) -> Vec<(i64, UserSlot)> {
+        self.lock().iter().map(|(user_id, slot)| (*user_id, slot.clone())).collect()
+    }
+
+    // Descarta a entrada do usuário se ela estiver vazia e mais ninguém a tiver em mãos. Como as cópias
+    // só são obtidas com o mapa travado, uma entrada descartada não pode estar prestes a ser gravada
+    pub(crate) fn forget(&self, user_id: i64) {
+        let mut slots = self.lock();
+        if slots.get(&user_id).is_some_and(esquecivel) {
+            slots.remove(&user_id);
+        }
+    }
+
+    // O mesmo para todas as entradas, na varredura periódica
+    pub(crate) fn forget_idle(&self) {
+        self.lock().retain(|_, slot| !esquecivel(slot));
+    }
+
+    pub(crate) async fn contains(&self, user_id: i64) -> bool {
+        match self.get(user_id) {
+            Some(slot) => slot.lock().await.is_some(),
+            None => false,
+        }
+    }
+
+    // Quantos usuários estão no meio de uma conversa
+    pub(crate) async fn active_count(&self) -> usize {
+        let mut active = 0;
+        for (_, slot) in self.slots() {
+            if slot.lock().await.is_some() {
+                active += 1;
+            }
+        }
+        active
+    }
+
+    // Cópia de todas as conversas abertas, para gravar no encerramento
+    pub(crate) async fn snapshot(&self) -> HashMap<i64, Sessao> {
+        let mut sessions = HashMap::new();
+        for (user_id, slot) in self.slots() {
+            if let Some(sessao) = slot.lock().await.clone() {
+                sessions.insert(user_id, sessao);
+            }
+        }
+        sessions
+    }
+
+    pub(crate) async fn restore(&self, sessions: HashMap<i64, Sessao>) {
+        for (user_id, sessao) in sessions {
+            *self.slot(user_id).lock().await = Some(sessao);
+        }
+    }
+
+    // Abre uma conversa nova, substituindo a que houver
+    pub(crate) async fn insert(&self, user_id: i64, state: UserState) {
+        *self.slot(user_id).lock().await = Some(Sessao::nova(state));
+    }
+}
+
+fn esquecivel(slot: &UserSlot) -> bool {
+    Arc::strong_count(slot) == 1 && slot.try_lock().is_ok_and(|sessao| sessao.is_none())
+}
+
+// Tempo que a fila de um chat fica ociosa antes de ser descartada
+const CHAT_QUEUE_IDLE: Duration = Duration::from_secs(60);
+
+// Uma fila serial por chat: as mensagens de um mesmo chat são tratadas na ordem de chegada,
+// enquanto chats diferentes continuam sendo atendidos em paralelo
+#[derive(Default)]
+pub struct ChatQueues {
+    queues: std::sync::Mutex<HashMap<i64, mpsc::UnboundedSender<QueuedMessage>>>,
+    // Acompanha os workers para que o encerramento espere o trabalho em andamento
+    workers: TaskTracker,
+}
+
+struct QueuedMessage {
+    update_id: u32,
+    message: Message,
+}
+
+impl ChatQueues {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<i64, mpsc::UnboundedSender<QueuedMessage>>> {
+        self.queues.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Não aceita mais mensagens; cada worker termina assim que esvaziar a sua fila
+    fn close(&self) {
+        self.workers.close();
+        self.lock().clear();
+    }
+
+    async fn wait_idle(&self) {
+        self.workers.wait().await;
+    }
+}
+
+// Tudo o que os handlers compartilham
+pub struct BotContext {
+    pub bot: Bot,
+    pub config: Config,
+    pub storage: Storage,
+    pub metrics: Metrics,
+    pub(crate) user_states: UserStates,
+    pub(crate) chat_queues: ChatQueues,
+    pub(crate) outbox: Outbox,
+    // Nome de usuário do bot, para ignorar comandos dirigidos a outros bots; conhecido ao iniciar
+    pub(crate) username: OnceLock<String>,
+}
+
+impl BotContext {
+    // Monta o contexto e liga a ele a fila de saída
+    pub fn start(config: Config) -> Arc<BotContext> {
+        let ctx = Arc::new(BotContext {
+            bot: Bot::new_url(config.bot_api_url()),
+            storage: Storage::new(&config.data_dir),
+            config,
+            user_states: UserStates::default(),
+            chat_queues: ChatQueues::default(),
+            metrics: Metrics::default(),
+            outbox: Outbox::default(),
+            username: OnceLock::new(),
+        });
+        Outbox::start(&ctx);
+        ctx
+    }
+}
+
+// Coloca a mensagem na fila do seu chat, criando a fila (e seu worker) se necessário
+pub fn dispatch_message(ctx: &Arc<BotContext>, update_id: u32, message: Message) {
+    let chat_id = message.chat.id;
+    if ctx.chat_queues.workers.is_closed() {
+        warn!(update_id, chat_id, "Mensagem recebida durante o encerramento; ignorada");
+        return;
+    }
+    let queued = QueuedMessage { update_id, message };
+    let mut queues = ctx.chat_queues.lock();
+    let queued = match queues.get(&chat_id) {
+        Some(sender) => match sender.send(queued) {
+            Ok(()) => return,
+            Err(mpsc::error::SendError(queued)) => queued,
+        },
+        None => queued,
+    };
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    sender.send(queued).expect("receiver is alive");
+    queues.insert(chat_id, sender);
+    ctx.chat_queues.workers.spawn(run_chat_queue(Arc::clone(ctx), chat_id, receiver));
+}
+
+// Cliques em botões não têm ordem a preservar: cada um roda numa tarefa própria, acompanhada
+// junto com os workers para que o encerramento espere por ela
+pub fn dispatch_callback(ctx: &Arc<BotContext>, update_id: u32, query: CallbackQuery) {
+    if ctx.chat_queues.workers.is_closed() {
+        warn!(update_id, "Botão clicado durante o encerramento; ignorado");
+        return;
+    }
+    let span = callback_span(update_id, &query);
+    let task_ctx = Arc::clone(ctx);
+    ctx.chat_queues.workers.spawn(async move { process_callback(query, &task_ctx).await }.instrument(span));
+}
+
+async fn run_chat_queue(ctx: Arc<BotContext>, chat_id: i64, mut receiver: mpsc::UnboundedReceiver<QueuedMessage>) {
+    loop {
+        match tokio::time::timeout(CHAT_QUEUE_IDLE, receiver.recv()).await {
+            Ok(Some(queued)) => {
+                let span = update_span(queued.update_id, &queued.message);
+                process_message(queued.message, &ctx).instrument(span).await;
+            }
+            Ok(None) => return,
+            Err(_) => {
+                // Com o mapa travado ninguém consegue enfileirar, então é seguro encerrar se a fila estiver vazia
+                let mut queues = ctx.chat_queues.lock();
+                if receiver.is_empty() {
+                    queues.remove(&chat_id);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// Confere se o token é aceito e se o bot enxerga cada grupo configurado
+pub async fn check_config(config: &Config, loaded_from: Option<&Path>) -> bool {
+    match loaded_from {
+        Some(path) => println!("Configuração carregada de {} (com variáveis de ambiente por cima).", path.display()),
+        None => println!("Configuração carregada apenas de variáveis de ambiente."),
+    }
+    println!("API do Telegram: {}", config.telegram.api_url);
+    println!("Diretório de dados: {}", config.data_dir.display());
+
+    let bot = Bot::new_url(config.bot_api_url());
+    match bot.get_me().await {
+        Ok(me) => println!("Token válido: @{}", me.result.username.as_deref().unwrap_or("?")),
+        Err(e) => {
+            println!("Token recusado pelo Telegram: {}", e);
+            return false;
+        }
+    }
+
+    let mut all_reachable = true;
+    for (nome, id) in config.grupos.all() {
+        let params = GetChatParams::builder().chat_id(id).build();
+        match bot.get_chat(&params).await {
+            Ok(chat) => println!("  grupo {:<5} {:>16}: ok ({})", nome, id, chat.result.title.as_deref().unwrap_or("sem título")),
+            Err(e) => {
+                all_reachable = false;
+                println!("  grupo {:<5} {:>16}: INACESSÍVEL ({})", nome, id, e);
+            }
+        }
+    }
+    all_reachable
+}
+
+// Tempo máximo que o encerramento espera pelas mensagens em andamento
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub async fn run_bot(config: Config) {
+    let ctx = BotContext::start(config);
+
+    if !ctx.config.metricas.endereco.is_empty() {
+        metrics::serve(&ctx.config.metricas.endereco, Arc::clone(&ctx)).await;
+    }
+
+    if let Err(e) = set_menu_commands(&ctx.bot).await {
+        warn!(error = %e, "Falha ao configurar o menu de comandos");
+    }
+    match ctx.bot.get_me().await {
+        Ok(me) => {
+            if let Some(username) = me.result.username {
+                let _ = ctx.username.set(username);
+            }
+        }
+        Err(e) => warn!(error = %e, "Falha ao consultar o nome do bot; comandos para outros bots não serão filtrados"),
+    }
+
+    ctx.storage.inicializar_times();
+    ctx.storage.remove_partial_downloads();
+    match ctx.storage.take_saved_states() {
+        Ok(states) if !states.is_empty() => {
+            info!(count = states.len(), "Conversas restauradas do último encerramento");
+            ctx.user_states.restore(states).await;
+        }
+        Ok(_) => {}
+        Err(e) => error!(error = %e, "Falha ao restaurar as conversas salvas"),
+    }
+
+    let recorder = match ctx.config.gravacao.arquivo.as_str() {
+        "" => None,
+        arquivo => match UpdateRecorder::open(arquivo) {
+            Ok(recorder) => {
+                info!(arquivo, "Gravando as atualizações recebidas");
+                Some(recorder)
+            }
+            Err(e) => {
+                error!(error = %e, "Falha ao abrir o arquivo de gravação; seguindo sem gravar");
+                None
+            }
+        },
+    };
+
+    let expiry = tokio::spawn({
+        let ctx = Arc::clone(&ctx);
+        async move { conversa::expirar_conversas(&ctx).await }
+    });
+
+    let mut update_params = GetUpdatesParams::builder().build();
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    info!("Yuan Shao Bot está de prontidão!");
+
+    loop {
+        let result = tokio::select! {
+            _ = &mut shutdown => break,
+            result = ctx.bot.get_updates(&update_params) => result,
+        };
+        match result {
+            Ok(response) => {
+                ctx.metrics.poll_succeeded();
+                for update in response.result {
+                    if let Some(recorder) = &recorder {
+                        recorder.record(&update);
+                    }
+                    match update.content {
+                        UpdateContent::Message(message) => dispatch_message(&ctx, update.update_id, *message),
+                        UpdateContent::CallbackQuery(query) => dispatch_callback(&ctx, update.update_id, *query),
+                        _ => {}
+                    }
+                    update_params.offset = Some((update.update_id + 1) as i64);
+                }
+            }
+            Err(error) => {
+                ctx.metrics.telegram_error("getUpdates");
+                error!(error = %error, "Falha ao buscar atualizações");
+                sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+
+    expiry.abort();
+    shutdown_gracefully(&ctx).await;
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!(error = %e, "Falha ao escutar Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!(error = %e, "Falha ao escutar SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+// Para de aceitar mensagens, espera as que estão em andamento, envia o que resta na fila de saída
+// e grava as conversas abertas para que sejam retomadas na próxima execução
+async fn shutdown_gracefully(ctx: &BotContext) {
+    info!("Encerrando: aguardando as mensagens em andamento");
+    ctx.chat_queues.close();
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, ctx.chat_queues.wait_idle()).await.is_err() {
+        warn!(timeout_secs = SHUTDOWN_TIMEOUT.as_secs(), "Mensagens ainda em andamento ao fim do prazo de encerramento");
+    }
+
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, ctx.outbox.flush()).await.is_err() {
+        warn!("A fila de saída não foi totalmente enviada");
+    }
+
+    let states = ctx.user_states.snapshot().await;
+    match ctx.storage.save_states(&states) {
+        Ok(()) => info!(count = states.len(), "Conversas abertas salvas"),
+        Err(e) => error!(error = %e, "Falha ao salvar as conversas abertas"),
+    }
+    info!("Yuan Shao Bot se retira. Até a próxima batalha!");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+    use serde_json::json;
+
+    const TOKEN: &str = "123:TESTE";
+    const ADMIN_GROUP: i64 = -100;
+    const SHU_GROUP: i64 = -200;
+
+    fn private_message(user_id: i64, extra: serde_json::Value) -> Message {
+        let mut message = json!({
+            "message_id": 1,
+            "date": 0,
+            "chat": { "id": user_id, "type": "private" },
+            "from": { "id": user_id, "is_bot": false, "first_name": format!("Guerreiro {}", user_id) },
+        });
+        message.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(message).unwrap()
+    }
+
+    fn text(user_id: i64, text: &str) -> Message {
+        private_message(user_id, json!({ "text": text }))
+    }
+
+    fn photo(user_id: i64, file_id: &str) -> Message {
+        private_message(user_id, json!({
+            "photo": [{ "file_id": file_id, "file_unique_id": file_id, "width": 10, "height": 10 }]
+        }))
+    }
+
+    fn admin_text(admin_id: i64, text: &str) -> Message {
+        serde_json::from_value(json!({
+            "message_id": admin_id,
+            "date": 0,
+            "chat": { "id": ADMIN_GROUP, "type": "supergroup", "title": "Conselho" },
+            "from": { "id": 1000 + admin_id, "is_bot": false, "first_name": format!("Conselheiro {}", admin_id) },
+            "text": text,
+        }))
+        .unwrap()
+    }
+
+    // Mensagem que o Telegram falso devolve a cada envio
+    fn sent() -> serde_json::Value {
+        json!({ "message_id": 7, "date": 0, "chat": { "id": ADMIN_GROUP, "type": "supergroup" }, "text": "ok" })
+    }
+
+    // Telegram falso que aceita sendMessage e o contexto do bot apontado para ele, com os dados num
+    // diretório temporário. Cada teste acrescenta ao servidor os outros métodos de que precisa
+    async fn setup() -> (mockito::ServerGuard, Arc<BotContext>, tempfile::TempDir) {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("POST", Matcher::Regex(r"^/bot[^/]+/sendMessage$".to_string()))
+            .with_body(json!({ "ok": true, "result": sent() }).to_string())
+            .create_async()
+            .await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = Config {
+            data_dir: data_dir.path().to_path_buf(),
+            ..Config::default()
+        };
+        config.telegram.token = TOKEN.to_string();
+        config.telegram.api_url = server.url();
+        config.grupos.admin = ADMIN_GROUP;
+        config.grupos.shu = SHU_GROUP;
+        let ctx = BotContext::start(config);
+        ctx.storage.inicializar_times();
+        (server, ctx, data_dir)
+    }
+
+    // getFile e o download de uma foto JPEG, esperados `vezes` vezes
+    async fn mock_downloads(server: &mut mockito::ServerGuard, vezes: usize) {
+        server.mock("POST", format!("/bot{}/getFile", TOKEN).as_str())
+            .with_body(json!({
+                "ok": true,
+                "result": { "file_id": "f", "file_unique_id": "f", "file_path": "photos/file.jpg" }
+            }).to_string())
+            .expect(vezes)
+            .create_async()
+            .await;
+        server.mock("GET", format!("/file/bot{}/photos/file.jpg", TOKEN).as_str())
+            .with_body(b"\xFF\xD8\xFF\xE0imagem")
+            .expect(vezes)
+            .create_async()
+            .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn many_users_upload_at_once() {
+        const USERS: i64 = 40;
+        const PHOTOS: usize = 3;
+
+        let (mut server, ctx, _data_dir) = setup().await;
+        let album = json!({
+            "ok": true,
+            "result": (0..PHOTOS).map(|_| sent()).collect::<Vec<_>>()
+        });
+        server.mock("POST", format!("/bot{}/sendMediaGroup", TOKEN).as_str())
+            .with_body(album.to_string())
+            .expect(USERS as usize)
+            .create_async()
+            .await;
+        mock_downloads(&mut server, (USERS as usize) * PHOTOS).await;
+
+        let tasks: Vec<_> = (1..=USERS)
+            .map(|user_id| {
+                let ctx = Arc::clone(&ctx);
+                tokio::spawn(async move {
+                    process_message(text(user_id, "/entregarmissao"), &ctx).await;
+                    process_message(text(user_id, "Shu"), &ctx).await;
+                    process_message(text(user_id, "1 pedra"), &ctx).await;
+                    for i in 0..PHOTOS {
+                        process_message(photo(user_id, &format!("foto-{}-{}", user_id, i)), &ctx).await;
+                    }
+                    process_message(text(user_id, &format!("https://t.me/grupo/{}", user_id)), &ctx).await;
+                    process_message(text(user_id, "/entregar"), &ctx).await;
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let entregas = ctx.storage.read_entregas("shu").unwrap();
+        assert_eq!(entregas.len(), USERS as usize);
+        let mut numeros: Vec<u32> = entregas.iter().map(|entrega| entrega.id).collect();
+        numeros.sort();
+        assert_eq!(numeros, (1..=USERS as u32).collect::<Vec<_>>());
+        for entrega in &entregas {
+            assert_eq!(entrega.imagens.len(), PHOTOS);
+            assert_eq!(entrega.textos, entrega.partidas);
+            assert_eq!(entrega.partidas.len(), 1);
+            for imagem in &entrega.imagens {
+                assert!(ctx.storage.path(imagem).exists(), "imagem ausente: {}", imagem);
+            }
+        }
+        for user_id in 1..=USERS {
+            assert!(!ctx.user_states.contains(user_id).await);
+        }
+        // As conversas encerradas não deixam entrada para trás
+        assert!(ctx.user_states.slots().is_empty());
+    }
+
+    #[tokio::test]
+    async fn oversize_files_and_files_past_the_limit_are_refused() {
+        let (mut server, ctx, _data_dir) = setup().await;
+        let recusas = server.mock("POST", format!("/bot{}/sendMessage", TOKEN).as_str())
+            .match_body(Matcher::Regex("passa de 20 MB|Já recebi 10 arquivos".to_string()))
+            .with_body(json!({ "ok": true, "result": sent() }).to_string())
+            .expect(2)
+            .create_async()
+            .await;
+        // O getFile já informa um tamanho acima do limite: o arquivo nem é baixado
+        server.mock("POST", format!("/bot{}/getFile", TOKEN).as_str())
+            .match_body(Matcher::Regex("grande".to_string()))
+            .with_body(json!({
+                "ok": true,
+                "result": { "file_id": "grande", "file_unique_id": "grande", "file_size": 21 * 1024 * 1024, "file_path": "photos/grande.jpg" }
+            }).to_string())
+            .create_async()
+            .await;
+
+        process_message(text(5, "/entregarmissao"), &ctx).await;
+        process_message(text(5, "Shu"), &ctx).await;
+        process_message(text(5, "1 pedra"), &ctx).await;
+        process_message(photo(5, "grande"), &ctx).await;
+
+        mock_downloads(&mut server, 10).await;
+        for i in 0..11 {
+            process_message(photo(5, &format!("foto-{}", i)), &ctx).await;
+        }
+        ctx.outbox.flush().await;
+        recusas.assert_async().await;
+
+        let Some(UserState::Entrega(conversa::EntregaConversa::AguardandoEntregas(coleta))) = conversa::atual(5, &ctx).await else {
+            panic!("a entrega deveria seguir aberta");
+        };
+        assert_eq!(coleta.entregas.iter().filter(|prova| prova.caminho().is_some()).count(), 10);
+    }
+
+    // Abre uma entrega do usuário 5 com uma foto e devolve o arquivo baixado
+    async fn entrega_com_foto(ctx: &BotContext) -> String {
+        process_message(text(5, "/entregarmissao"), ctx).await;
+        process_message(text(5, "Shu"), ctx).await;
+        process_message(text(5, "1 pedra"), ctx).await;
+        process_message(photo(5, "foto"), ctx).await;
+        let Some(conversa) = conversa::atual(5, ctx).await else {
+            panic!("a entrega deveria estar aberta");
+        };
+        conversa.arquivos().pop().expect("a foto deveria ter sido baixada")
+    }
+
+    #[tokio::test]
+    async fn idle_deliveries_get_a_reminder_then_become_drafts_that_expire() {
+        let (mut server, ctx, _data_dir) = setup().await;
+        mock_downloads(&mut server, 1).await;
+        let arquivo = entrega_com_foto(&ctx).await;
+        let atividade = ctx.user_states.snapshot().await[&5].atividade;
+        let rascunho = |ctx: &BotContext| ctx.storage.read_json::<serde_json::Value>("rascunhos.json").ok().map(|r| r["5"].clone());
+
+        // Padrão: lembrete 5 minutos antes da expiração, aos 30 minutos, e rascunho guardado por 7 dias
+        conversa::varrer(&ctx, atividade + 24 * 60).await;
+        assert!(!ctx.user_states.snapshot().await[&5].lembrete_enviado);
+        conversa::varrer(&ctx, atividade + 25 * 60).await;
+        assert!(ctx.user_states.snapshot().await[&5].lembrete_enviado);
+
+        let expirou = atividade + 30 * 60;
+        conversa::varrer(&ctx, expirou).await;
+        assert!(!ctx.user_states.contains(5).await);
+        assert!(ctx.user_states.slots().is_empty());
+        assert_eq!(rascunho(&ctx).unwrap()["salvo_em"], expirou);
+        assert!(ctx.storage.path(&arquivo).exists());
+
+        conversa::varrer(&ctx, expirou + 7 * 24 * 60 * 60).await;
+        assert!(rascunho(&ctx).unwrap().is_object());
+        conversa::varrer(&ctx, expirou + 7 * 24 * 60 * 60 + 1).await;
+        assert!(rascunho(&ctx).unwrap().is_null());
+        assert!(!ctx.storage.path(&arquivo).exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn the_sweep_leaves_a_delivery_being_registered_alone() {
+        let (mut server, ctx, _data_dir) = setup().await;
+        mock_downloads(&mut server, 1).await;
+        let arquivo = entrega_com_foto(&ctx).await;
+
+        // A varredura roda, bem depois do prazo, enquanto o álbum da entrega já registrada vai aos admins
+        let runtime = tokio::runtime::Handle::current();
+        let varredura_ctx = Arc::clone(&ctx);
+        let album = json!({ "ok": true, "result": [sent()] }).to_string();
+        server.mock("POST", format!("/bot{}/sendMediaGroup", TOKEN).as_str())
+            .with_body_from_request(move |_| {
+                let (runtime, ctx) = (runtime.clone(), Arc::clone(&varredura_ctx));
+                std::thread::spawn(move || runtime.block_on(conversa::varrer(&ctx, i64::MAX / 2)))
+                    .join()
+                    .unwrap();
+                album.clone().into_bytes()
+            })
+            .expect(1)
+            .create_async()
+            .await;
+
+        process_message(text(5, "https://t.me/grupo/5"), &ctx).await;
+        process_message(text(5, "/entregar"), &ctx).await;
+
+        let entrega = ctx.storage.buscar_entrega(1).unwrap().unwrap();
+        assert_eq!(entrega.imagens, std::slice::from_ref(&arquivo));
+        assert!(ctx.storage.take_rascunho(5).unwrap().is_none());
+        assert!(ctx.storage.path(&arquivo).exists());
+        assert!(!ctx.user_states.contains(5).await);
+    }
+
+    #[tokio::test]
+    async fn captions_get_the_same_link_checks_as_texts() {
+        let (mut server, ctx, _data_dir) = setup().await;
+        let mut anterior = crate::domain::Entrega::from_provas("Zhao".to_string(), "zhao".to_string(), "shu".to_string(), &[]);
+        anterior.missao = Some(crate::domain::MissaoCumprida { naipe: 1, tipo: "pedra".to_string() });
+        anterior.partidas = vec!["https://t.me/grupo/5".to_string()];
+        ctx.storage.append_entrega(anterior).unwrap();
+
+        let recusas = server.mock("POST", format!("/bot{}/sendMessage", TOKEN).as_str())
+            .match_body(Matcher::Regex("mas não a legenda".to_string()))
+            .with_body(json!({ "ok": true, "result": sent() }).to_string())
+            .expect(2)
+            .create_async()
+            .await;
+        mock_downloads(&mut server, 3).await;
+        let com_legenda = |file_id: &str, caption: &str| {
+            private_message(6, json!({
+                "photo": [{ "file_id": file_id, "file_unique_id": file_id, "width": 10, "height": 10 }],
+                "caption": caption,
+            }))
+        };
+
+        process_message(text(6, "/entregarmissao"), &ctx).await;
+        process_message(text(6, "Shu"), &ctx).await;
+        process_message(text(6, "1 pedra"), &ctx).await;
+        process_message(com_legenda("repetida", "https://t.me/grupo/5"), &ctx).await;
+        process_message(com_legenda("quebrada", "t.me/grupo"), &ctx).await;
+        process_message(com_legenda("nova", "https://t.me/grupo/6"), &ctx).await;
+        ctx.outbox.flush().await;
+        recusas.assert_async().await;
+
+        let Some(UserState::Entrega(conversa::EntregaConversa::AguardandoEntregas(coleta))) = conversa::atual(6, &ctx).await else {
+            panic!("a entrega deveria seguir aberta");
+        };
+        let textos: Vec<&crate::domain::Prova> = coleta.entregas.iter().filter(|prova| prova.caminho().is_none()).collect();
+        assert_eq!(coleta.entregas.len(), 4);
+        assert_eq!(textos, [&crate::domain::Prova::Texto { texto: "https://t.me/grupo/6".to_string() }]);
+    }
+
+    #[tokio::test]
+    async fn cancel_only_works_in_private_and_for_this_bot() {
+        let (_server, ctx, _data_dir) = setup().await;
+        ctx.username.set("yuanshao_bot".to_string()).unwrap();
+        process_message(text(5, "/entregarmissao"), &ctx).await;
+
+        let no_grupo = private_message(5, json!({
+            "chat": { "id": -200, "type": "supergroup", "title": "Shu" },
+            "text": "/cancelar",
+        }));
+        process_message(no_grupo, &ctx).await;
+        process_message(text(5, "/cancelar@outro_bot"), &ctx).await;
+        assert!(ctx.user_states.contains(5).await);
+
+        process_message(text(5, "/cancelar@YuanShao_bot"), &ctx).await;
+        assert!(!ctx.user_states.contains(5).await);
+    }
+
+    #[tokio::test]
+    async fn summary_reaches_admins_when_the_captioned_album_fails() {
+        let (mut server, ctx, _data_dir) = setup().await;
+        // O álbum com a legenda é recusado; sem ela, passa
+        let album = json!({ "ok": true, "result": [sent()] }).to_string();
+        server.mock("POST", format!("/bot{}/sendMediaGroup", TOKEN).as_str())
+            .with_status_code_from_request(|request| if request.utf8_lossy_body().unwrap().contains("caption") { 400 } else { 200 })
+            .with_body_from_request(move |request| {
+                if request.utf8_lossy_body().unwrap().contains("caption") {
+                    br#"{"ok":false,"error_code":400,"description":"Bad Request: message caption is too long"}"#.to_vec()
+                } else {
+                    album.clone().into_bytes()
+                }
+            })
+            .expect(2)
+            .create_async()
+            .await;
+        mock_downloads(&mut server, 1).await;
+
+        process_message(text(5, "/entregarmissao"), &ctx).await;
+        process_message(text(5, "Shu"), &ctx).await;
+        process_message(text(5, "1 pedra"), &ctx).await;
+        process_message(photo(5, "foto"), &ctx).await;
+        process_message(text(5, "https://t.me/grupo/5"), &ctx).await;
+        process_message(text(5, "/entregar"), &ctx).await;
+
+        let entrega = ctx.storage.buscar_entrega(1).unwrap().unwrap();
+        assert!(!entrega.legenda_admin);
+        assert_eq!(entrega.mensagens_admin, [7, 7]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_approvals_credit_once() {
+        const ADMINS: i64 = 8;
+        let (_server, ctx, _data_dir) = setup().await;
+
+        let mut entrega = crate::domain::Entrega::from_provas("Zhao".to_string(), "zhao".to_string(), "shu".to_string(), &[]);
+        entrega.user_id = Some(5);
+        entrega.missao = Some(crate::domain::MissaoCumprida { naipe: 1, tipo: "pedra".to_string() });
+        let id = ctx.storage.append_entrega(entrega).unwrap();
+
+        // Vários admins aprovam a mesma entrega ao mesmo tempo; só a primeira aprovação vale
+        let tasks: Vec<_> = (1..=ADMINS)
+            .map(|admin_id| {
+                let ctx = Arc::clone(&ctx);
+                let message = admin_text(admin_id, &format!("/aprovar {}", id));
+                tokio::spawn(async move { process_message(message, &ctx).await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(ctx.storage.read_time_db("shu").unwrap().naipes[0].pedra, 1);
+        let entrega = ctx.storage.buscar_entrega(id).unwrap().unwrap();
+        assert_eq!(entrega.status, crate::domain::StatusEntrega::Aprovada);
+    }
+
+    #[tokio::test]
+    async fn crediting_a_delivery_with_add_names_the_player() {
+        let (mut server, ctx, _data_dir) = setup().await;
+        let mut entrega = crate::domain::Entrega::from_provas("Zhao".to_string(), "zhao".to_string(), "shu".to_string(), &[]);
+        entrega.user_id = Some(5);
+        let id = ctx.storage.append_entrega(entrega).unwrap();
+
+        let enviada = |server: &mut mockito::ServerGuard, chat_id: i64, trecho: &str| {
+            server.mock("POST", format!("/bot{}/sendMessage", TOKEN).as_str())
+                .match_body(Matcher::AllOf(vec![
+                    Matcher::PartialJson(json!({ "chat_id": chat_id })),
+                    Matcher::Regex(regex::escape(trecho)),
+                ]))
+                .with_body(json!({ "ok": true, "result": sent() }).to_string())
+                .expect(1)
+                .create()
+        };
+        let anuncio = enviada(&mut server, SHU_GROUP, "Zhao cumpriu a missão do naipe 3 (Pedra)");
+        let aviso = enviada(&mut server, 5, &format!("Sua entrega Nº {}, da missão do naipe 3 (Pedra), foi aprovada", id));
+
+        // O time do comando tem de ser o da entrega
+        process_message(admin_text(1, &format!("/add wei 3 pedra {}", id)), &ctx).await;
+        assert_eq!(ctx.storage.read_time_db("wei").unwrap().naipes[2].pedra, 0);
+        assert!(ctx.storage.buscar_entrega(id).unwrap().unwrap().missao.is_none());
+
+        process_message(admin_text(1, &format!("/add shu 3 pedra {}", id)), &ctx).await;
+        ctx.outbox.flush().await;
+        anuncio.assert_async().await;
+        aviso.assert_async().await;
+        assert_eq!(ctx.storage.read_time_db("shu").unwrap().naipes[2].pedra, 1);
+        let entrega = ctx.storage.buscar_entrega(id).unwrap().unwrap();
+        assert_eq!(entrega.status, crate::domain::StatusEntrega::Aprovada);
+        assert_eq!(entrega.missao, Some(crate::domain::MissaoCumprida { naipe: 3, tipo: "pedra".to_string() }));
+    }
+}
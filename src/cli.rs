@@ -0,0 +1,331 @@
+// Subcomandos de administração que trabalham direto nos arquivos de dados, sem o Telegram.
+// Todos passam pelo mesmo Storage usado pelos handlers do bot
+use serde_json::json;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::domain::{NAIPES, TIMES};
+use crate::error::{BotError, StorageError};
+use crate::format::format_team_db;
+use crate::parsing::parse_missoes_emojis;
+use crate::storage::Storage;
+
+pub fn check_team(time: &str) -> Result<&str, BotError> {
+    TIMES
+        .iter()
+        .copied()
+        .find(|t| *t == time.to_lowercase())
+        .ok_or_else(|| BotError::Parse(format!("time desconhecido: {} (use shu, wei ou wu)", time)))
+}
+
+// Confere cada arquivo de dados e o texto das missões. Devolve false se algum problema foi encontrado
+pub fn validate(storage: &Storage) -> bool {
+    let mut ok = true;
+    for (file, problems) in relatorio(storage) {
+        if problems.is_empty() {
+            println!("{:<22} ok", file);
+        } else {
+            ok = false;
+            for problem in problems {
+                println!("{:<22} {}", file, problem);
+            }
+        }
+    }
+    ok
+}
+
+// Os problemas de cada arquivo, na ordem em que são conferidos
+fn relatorio(storage: &Storage) -> Vec<(String, Vec<String>)> {
+    let mut relatorio = vec![
+        ("inscritos.json".to_string(), validate_inscritos(storage)),
+        ("missoes.json".to_string(), validate_missoes(storage)),
+    ];
+    for time in TIMES {
+        relatorio.push((format!("{}.json", time), validate_time_db(storage, time)));
+        relatorio.push((format!("registro_{}.json", time), validate_entregas(storage, time)));
+    }
+    for file in ["estados.json", "rascunhos.json"] {
+        if storage.path(file).exists() {
+            let problems = match storage.read_json::<serde_json::Value>(file) {
+                Ok(_) => Vec::new(),
+                Err(e) => vec![e.to_string()],
+            };
+            relatorio.push((file.to_string(), problems));
+        }
+    }
+    relatorio
+}
+
+fn validate_inscritos(storage: &Storage) -> Vec<String> {
+    let inscritos = match storage.read_inscritos() {
+        Ok(inscritos) => inscritos,
+        Err(e) => return vec![e.to_string()],
+    };
+    let mut problems = Vec::new();
+    let mut ids = HashSet::new();
+    let mut users = HashSet::new();
+    for inscrito in &inscritos {
+        if !ids.insert(inscrito.id) {
+            problems.push(format!("número de inscrição repetido: {}", inscrito.id));
+        }
+        if !users.insert(inscrito.user.to_lowercase()) {
+            problems.push(format!("usuário inscrito mais de uma vez: @{}", inscrito.user));
+        }
+    }
+    problems
+}
+
+fn validate_missoes(storage: &Storage) -> Vec<String> {
+    // read_missoes cria o arquivo quando ele não existe; aqui só se lê
+    if !storage.path("missoes.json").exists() {
+        return vec!["arquivo ausente".to_string()];
+    }
+    let missoes = match storage.read_missoes() {
+        Ok(missoes) => missoes,
+        Err(e) => return vec![e.to_string()],
+    };
+    let Some(first) = missoes.first() else {
+        return vec!["nenhuma missão cadastrada".to_string()];
+    };
+
+    let emojis = parse_missoes_emojis(&first.texto).emojis;
+    let mut problems = Vec::new();
+    for naipe in 1..=NAIPES {
+        match emojis.get(&naipe) {
+            None => problems.push(format!("naipe {} não encontrado no texto das missões", naipe)),
+            Some(tipos) => {
+                for tipo in ["Pedra", "Papel", "Tesoura"] {
+                    if !tipos.contains_key(tipo) {
+                        problems.push(format!("naipe {} sem missão {}", naipe, tipo));
+                    }
+                }
+            }
+        }
+    }
+    problems
+}
+
+fn validate_time_db(storage: &Storage, time: &str) -> Vec<String> {
+    match storage.read_time_db(time) {
+        Ok(db) if db.naipes.len() != NAIPES => {
+            vec![format!("esperados {} naipes, encontrados {}", NAIPES, db.naipes.len())]
+        }
+        Ok(db) if db.soldados < 0 => vec![format!("número de soldados negativo: {}", db.soldados)],
+        Ok(_) => Vec::new(),
+        Err(e) => vec![e.to_string()],
+    }
+}
+
+fn validate_entregas(storage: &Storage, time: &str) -> Vec<String> {
+    let entregas = match storage.read_entregas(time) {
+        Ok(entregas) => entregas,
+        Err(e) => return vec![e.to_string()],
+    };
+    let mut problems = Vec::new();
+    let mut numeros = HashSet::new();
+    for (i, entrega) in entregas.iter().enumerate() {
+        if entrega.id > 0 && !numeros.insert(entrega.id) {
+            problems.push(format!("entrega {} repete o número {}", i + 1, entrega.id));
+        }
+        if entrega.time != time {
+            problems.push(format!("entrega {} registrada para o time {}", i + 1, entrega.time));
+        }
+        for imagem in entrega.imagens.iter().chain(&entrega.videos) {
+            if !storage.path(imagem).is_file() {
+                problems.push(format!("entrega {} aponta para uma prova ausente: {}", i + 1, imagem));
+            }
+        }
+    }
+    problems
+}
+
+// Junta todos os dados do evento num único JSON, na saída padrão ou em um arquivo
+pub fn export(storage: &Storage, output: Option<&Path>) -> Result<(), BotError> {
+    let mut times = serde_json::Map::new();
+    for time in TIMES {
+        times.insert(
+            time.to_string(),
+            json!({
+                "banco": storage.read_time_db(time)?,
+                "entregas": storage.read_entregas(time)?,
+            }),
+        );
+    }
+    let export = json!({
+        "inscritos": storage.read_inscritos()?,
+        "missoes": storage.read_missoes()?,
+        "times": times,
+    });
+
+    let data = serde_json::to_string_pretty(&export).map_err(|e| BotError::Parse(format!("exportação: {}", e)))?;
+    match output {
+        Some(path) => {
+            fs::write(path, data).map_err(|source| StorageError::Io { path: path.to_path_buf(), source })?;
+            eprintln!("Dados exportados para {}", path.display());
+        }
+        None => println!("{}", data),
+    }
+    Ok(())
+}
+
+// Lê um CSV com as colunas nome,user (cabeçalho opcional) e acrescenta os inscritos
+pub fn import_inscritos(storage: &Storage, csv_path: &Path) -> Result<(), BotError> {
+    let data = fs::read_to_string(csv_path)
+        .map_err(|source| StorageError::Io { path: csv_path.to_path_buf(), source })?;
+
+    let (added, skipped) = storage.import_inscritos(ler_csv(&data)?)?;
+    for inscrito in &added {
+        println!("{:>4}  {}  (@{})", inscrito.id, inscrito.nome, inscrito.user);
+    }
+    println!("{} inscritos adicionados, {} já estavam inscritos.", added.len(), skipped);
+    Ok(())
+}
+
+// Pares (nome, user) do CSV, na ordem do arquivo
+fn ler_csv(data: &str) -> Result<Vec<(String, String)>, BotError> {
+    let mut novos = Vec::new();
+    for (i, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_csv_line(line);
+        let is_header = i == 0
+            && fields.first().is_some_and(|f| f.trim().eq_ignore_ascii_case("nome"));
+        if is_header {
+            continue;
+        }
+        match fields.as_slice() {
+            [nome, user, ..] if !nome.trim().is_empty() && !user.trim().is_empty() => {
+                novos.push((nome.clone(), user.clone()));
+            }
+            _ => return Err(BotError::Parse(format!("linha {} do CSV: esperado nome,user", i + 1))),
+        }
+    }
+    Ok(novos)
+}
+
+// Separa os campos de uma linha de CSV, aceitando campos entre aspas com vírgulas e "" escapadas
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+pub fn set_soldiers(storage: &Storage, time: &str, soldados: &str) -> Result<(), BotError> {
+    let time = check_team(time)?;
+    let soldados = soldados
+        .parse::<i32>()
+        .ok()
+        .filter(|n| *n >= 0)
+        .ok_or_else(|| BotError::Parse(format!("quantidade de soldados inválida: {}", soldados)))?;
+    let db = storage.set_soldados(time, soldados)?;
+    println!("Soldados do time {} atualizados. Total: {}", time.to_uppercase(), db.soldados);
+    Ok(())
+}
+
+pub fn show(storage: &Storage, time: &str) -> Result<(), BotError> {
+    let time = check_team(time)?;
+    println!("{}", format_team_db(storage, time)?);
+    Ok(())
+}
+
+// Substitui o antigo limpar_entregas.sh: apaga as provas e zera os registros de entregas dos três times
+pub fn reset(storage: &Storage, confirmed: bool) -> Result<(), BotError> {
+    if !confirmed {
+        return Err(BotError::Parse(
+            "reset apaga todas as provas e registros de entregas; repita com --yes para confirmar".to_string(),
+        ));
+    }
+    for time in TIMES {
+        let removed = storage.reset_entregas(time)?;
+        println!("Time {}: {} provas removidas, registro zerado.", time.to_uppercase(), removed);
+    }
+    // As provas dos rascunhos ficavam em entregas/ e acabaram de ser apagadas
+    let descartados = storage.clear_rascunhos()?;
+    if descartados > 0 {
+        println!("{} rascunhos descartados.", descartados);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Entrega;
+
+    #[test]
+    fn csv_fields_may_be_quoted_and_hold_commas() {
+        assert_eq!(split_csv_line("Liu Bei,liubei"), ["Liu Bei", "liubei"]);
+        assert_eq!(split_csv_line(r#""Cao, o Grande",caocao"#), ["Cao, o Grande", "caocao"]);
+        assert_eq!(split_csv_line(r#""Sun ""Tigre"" Jian",sunjian,"#), [r#"Sun "Tigre" Jian"#, "sunjian", ""]);
+    }
+
+    #[test]
+    fn csv_header_and_blank_lines_are_skipped() {
+        let novos = ler_csv("Nome,User\nLiu Bei,liubei\n\n\"Cao, o Grande\",@caocao\n").unwrap();
+        assert_eq!(
+            novos,
+            [
+                ("Liu Bei".to_string(), "liubei".to_string()),
+                ("Cao, o Grande".to_string(), "@caocao".to_string()),
+            ]
+        );
+        // Só a primeira linha pode ser cabeçalho, e uma linha sem user é recusada com o seu número
+        let erro = ler_csv("Liu Bei,liubei\nZhang Fei\n").unwrap_err().to_string();
+        assert!(erro.contains("linha 2"), "{}", erro);
+    }
+
+    #[test]
+    fn import_adds_the_csv_players_once() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(data_dir.path());
+        let csv = data_dir.path().join("inscritos.csv");
+        fs::write(&csv, "nome,user\nLiu Bei,liubei\n\"Cao, o Grande\",caocao\n").unwrap();
+        import_inscritos(&storage, &csv).unwrap();
+        import_inscritos(&storage, &csv).unwrap();
+        let inscritos = storage.read_inscritos().unwrap();
+        assert_eq!(inscritos.len(), 2);
+        assert_eq!(inscritos[1].nome, "Cao, o Grande");
+    }
+
+    #[test]
+    fn validate_reports_a_corrupted_registry() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(data_dir.path());
+        storage.inicializar_times();
+
+        let mut entrega = Entrega::from_provas("Zhao".to_string(), "zhao".to_string(), "shu".to_string(), &[]);
+        entrega.id = 7;
+        entrega.imagens = vec!["entregas/shu/sumiu.jpg".to_string()];
+        let mut repetida = entrega.clone();
+        repetida.imagens.clear();
+        repetida.time = "wu".to_string();
+        storage.write_entregas("shu", &[entrega, repetida]).unwrap();
+        fs::write(storage.path("wei.json"), "{ corrompido").unwrap();
+
+        let relatorio = relatorio(&storage);
+        let problemas = |file: &str| relatorio.iter().find(|(f, _)| f == file).map(|(_, p)| p.clone()).unwrap();
+        let registro = problemas("registro_shu.json");
+        assert!(registro.iter().any(|p| p == "entrega 1 aponta para uma prova ausente: entregas/shu/sumiu.jpg"), "{:?}", registro);
+        assert!(registro.iter().any(|p| p == "entrega 2 repete o número 7"), "{:?}", registro);
+        assert!(registro.iter().any(|p| p == "entrega 2 registrada para o time wu"), "{:?}", registro);
+        assert_eq!(problemas("wei.json").len(), 1);
+        assert!(problemas("registro_wu.json").is_empty());
+        assert!(!validate(&storage));
+    }
+}
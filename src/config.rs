@@ -0,0 +1,430 @@
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::BotError;
+use crate::logging::LogFormat;
+
+// Arquivo lido quando nem --config nem YUANSHAO_CONFIG são informados
+pub const DEFAULT_CONFIG_FILE: &str = "yuanshao.toml";
+
+// URL base da Bot API. Pode apontar para um servidor próprio (telegram-bot-api) ou para um dublê local em testes
+pub const DEFAULT_TELEGRAM_API_URL: &str = "https://api.telegram.org";
+
+// Configuração do bot, carregada uma única vez na inicialização
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub telegram: TelegramConfig,
+    pub data_dir: PathBuf,
+    pub grupos: GruposConfig,
+    pub log: LogConfig,
+    pub metricas: MetricasConfig,
+    pub gravacao: GravacaoConfig,
+    pub conversas: ConversasConfig,
+    pub emojis: EmojisConfig,
+    pub limites: LimitesConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelegramConfig {
+    pub token: String,
+    pub api_url: String,
+}
+
+// IDs dos grupos em que o bot atua
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GruposConfig {
+    pub admin: i64,
+    pub shu: i64,
+    pub wei: i64,
+    pub wu: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub formato: LogFormat,
+    // Filtro no formato do RUST_LOG; a própria variável RUST_LOG, se definida, tem precedência
+    pub nivel: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricasConfig {
+    // Endereço do servidor de /metrics e /healthz; vazio desliga o servidor
+    pub endereco: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GravacaoConfig {
+    // Arquivo JSONL em que cada atualização recebida é gravada, para o subcomando replay; vazio desliga
+    pub arquivo: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConversasConfig {
+    // Minutos sem resposta até a conversa ser encerrada; 0 desliga a expiração
+    pub expiracao_minutos: u64,
+    // Quantos minutos antes da expiração o usuário recebe um lembrete; 0 desliga o lembrete
+    pub lembrete_minutos: u64,
+    // Por quantos dias as provas de uma entrega expirada ficam guardadas como rascunho
+    pub rascunho_dias: u64,
+}
+
+// Emoji que cada jogador deve levar no nome para que suas missões contem; vazio desliga a checagem
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmojisConfig {
+    pub shu: String,
+    pub wei: String,
+    pub wu: String,
+}
+
+// Limites para os arquivos de prova
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitesConfig {
+    // Tamanho máximo de cada arquivo, em MB
+    pub tamanho_maximo_mb: u64,
+    pub arquivos_por_entrega: usize,
+    // Arquivos somados de todas as entregas de um jogador, incluindo a que está em andamento
+    pub arquivos_por_jogador: usize,
+}
+
+impl Default for LimitesConfig {
+    fn default() -> Self {
+        LimitesConfig {
+            tamanho_maximo_mb: 20,
+            arquivos_por_entrega: 10,
+            arquivos_por_jogador: 200,
+        }
+    }
+}
+
+// Maiores valores aceitos: 2000 MB é o maior arquivo que um servidor próprio da Bot API entrega, e
+// conversas ou rascunhos guardados por mais tempo que isso não fazem sentido num evento
+const MAX_TAMANHO_MB: u64 = 2000;
+const MAX_EXPIRACAO_MINUTOS: u64 = 7 * 24 * 60;
+const MAX_RASCUNHO_DIAS: u64 = 365;
+
+impl LimitesConfig {
+    pub fn tamanho_maximo_bytes(&self) -> u64 {
+        self.tamanho_maximo_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+impl ConversasConfig {
+    pub fn expiracao_segundos(&self) -> i64 {
+        segundos(self.expiracao_minutos, 60)
+    }
+
+    pub fn lembrete_segundos(&self) -> i64 {
+        segundos(self.lembrete_minutos, 60)
+    }
+
+    pub fn rascunho_segundos(&self) -> i64 {
+        segundos(self.rascunho_dias, 24 * 60 * 60)
+    }
+}
+
+// Os limites de validate mantêm as contas longe do estouro; a saturação cobre uma Config montada sem validação
+fn segundos(valor: u64, unidade: u64) -> i64 {
+    i64::try_from(valor.saturating_mul(unidade)).unwrap_or(i64::MAX)
+}
+
+impl Default for ConversasConfig {
+    fn default() -> Self {
+        ConversasConfig {
+            expiracao_minutos: 30,
+            lembrete_minutos: 5,
+            rascunho_dias: 7,
+        }
+    }
+}
+
+impl Default for MetricasConfig {
+    fn default() -> Self {
+        MetricasConfig {
+            endereco: "127.0.0.1:9090".to_string(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            telegram: TelegramConfig::default(),
+            data_dir: PathBuf::from("."),
+            grupos: GruposConfig::default(),
+            log: LogConfig::default(),
+            metricas: MetricasConfig::default(),
+            gravacao: GravacaoConfig::default(),
+            conversas: ConversasConfig::default(),
+            emojis: EmojisConfig::default(),
+            limites: LimitesConfig::default(),
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            formato: LogFormat::default(),
+            nivel: "info".to_string(),
+        }
+    }
+}
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        TelegramConfig {
+            token: String::new(),
+            api_url: DEFAULT_TELEGRAM_API_URL.to_string(),
+        }
+    }
+}
+
+impl Config {
+    // Lê o arquivo TOML (se houver), aplica as variáveis de ambiente por cima e valida o resultado.
+    // Um caminho explícito (--config ou YUANSHAO_CONFIG) precisa existir; o arquivo padrão é opcional
+    pub fn load(explicit_path: Option<&Path>) -> Result<(Config, Option<PathBuf>), BotError> {
+        let (config, path, mut problems) = Config::read(explicit_path)?;
+        problems.extend(config.validate());
+        if !problems.is_empty() {
+            return Err(BotError::Config(format!("\n  - {}", problems.join("\n  - "))));
+        }
+        Ok((config, path))
+    }
+
+    // Para os subcomandos que só mexem nos arquivos de dados: token e grupos não são exigidos
+    pub fn load_offline(explicit_path: Option<&Path>) -> Result<Config, BotError> {
+        let (config, _, mut problems) = Config::read(explicit_path)?;
+        problems.extend(config.validate_data_dir());
+        if !problems.is_empty() {
+            return Err(BotError::Config(format!("\n  - {}", problems.join("\n  - "))));
+        }
+        Ok(config)
+    }
+
+    fn read(explicit_path: Option<&Path>) -> Result<(Config, Option<PathBuf>, Vec<String>), BotError> {
+        let explicit_path = explicit_path
+            .map(Path::to_path_buf)
+            .or_else(|| env::var_os("YUANSHAO_CONFIG").map(PathBuf::from));
+
+        let path = match explicit_path {
+            Some(path) => Some(path),
+            None => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.exists()),
+        };
+
+        let mut config = match &path {
+            Some(path) => {
+                let data = fs::read_to_string(path)
+                    .map_err(|e| BotError::Config(format!("não foi possível ler {}: {}", path.display(), e)))?;
+                toml::from_str(&data)
+                    .map_err(|e| BotError::Config(format!("{} é inválido: {}", path.display(), e)))?
+            }
+            None => Config::default(),
+        };
+
+        let problems = config.apply_env();
+        Ok((config, path, problems))
+    }
+
+    // Variáveis de ambiente têm precedência sobre o arquivo
+    fn apply_env(&mut self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if let Ok(token) = env::var("TELEGRAM_BOT_TOKEN") {
+            self.telegram.token = token;
+        }
+        if let Ok(api_url) = env::var("TELEGRAM_API_URL") {
+            self.telegram.api_url = api_url;
+        }
+        if let Some(data_dir) = env::var_os("DATA_DIR") {
+            self.data_dir = PathBuf::from(data_dir);
+        }
+        if let Ok(endereco) = env::var("METRICS_ADDR") {
+            self.metricas.endereco = endereco.trim().to_string();
+        }
+        if let Ok(arquivo) = env::var("UPDATES_LOG") {
+            self.gravacao.arquivo = arquivo.trim().to_string();
+        }
+        if let Ok(formato) = env::var("LOG_FORMAT") {
+            match LogFormat::parse(&formato) {
+                Some(formato) => self.log.formato = formato,
+                None => problems.push(format!("LOG_FORMAT deve ser texto ou json: {:?}", formato)),
+            }
+        }
+
+        env_number("SESSION_TIMEOUT_MINUTES", &mut self.conversas.expiracao_minutos, &mut problems);
+        env_number("SESSION_REMINDER_MINUTES", &mut self.conversas.lembrete_minutos, &mut problems);
+        env_number("DRAFT_RETENTION_DAYS", &mut self.conversas.rascunho_dias, &mut problems);
+        env_number("MAX_FILE_MB", &mut self.limites.tamanho_maximo_mb, &mut problems);
+        env_number("MAX_FILES_PER_DELIVERY", &mut self.limites.arquivos_por_entrega, &mut problems);
+        env_number("MAX_FILES_PER_PLAYER", &mut self.limites.arquivos_por_jogador, &mut problems);
+
+        let emojis = [
+            ("SHU_EMOJI", &mut self.emojis.shu),
+            ("WEI_EMOJI", &mut self.emojis.wei),
+            ("WU_EMOJI", &mut self.emojis.wu),
+        ];
+        for (var, target) in emojis {
+            if let Ok(value) = env::var(var) {
+                *target = value;
+            }
+        }
+        for emoji in [&mut self.emojis.shu, &mut self.emojis.wei, &mut self.emojis.wu] {
+            *emoji = emoji.trim().to_string();
+        }
+
+        let grupos = [
+            ("ADMIN_GROUP_ID", &mut self.grupos.admin),
+            ("SHU_GROUP_ID", &mut self.grupos.shu),
+            ("WEI_GROUP_ID", &mut self.grupos.wei),
+            ("WU_GROUP_ID", &mut self.grupos.wu),
+        ];
+        for (var, target) in grupos {
+            if let Ok(value) = env::var(var) {
+                match value.trim().parse() {
+                    Ok(id) => *target = id,
+                    Err(_) => problems.push(format!("{} não é um ID de chat válido: {:?}", var, value)),
+                }
+            }
+        }
+
+        self.telegram.token = self.telegram.token.trim().to_string();
+        self.telegram.api_url = self.telegram.api_url.trim().trim_end_matches('/').to_string();
+        problems
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let token = &self.telegram.token;
+        if token.is_empty() {
+            problems.push("token do bot ausente (telegram.token ou TELEGRAM_BOT_TOKEN)".to_string());
+        } else if !token
+            .split_once(':')
+            .is_some_and(|(id, secret)| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) && !secret.is_empty())
+        {
+            problems.push("token do bot em formato inesperado; esperado <id>:<segredo>".to_string());
+        }
+
+        let api_url = &self.telegram.api_url;
+        if !(api_url.starts_with("http://") || api_url.starts_with("https://")) {
+            problems.push(format!("telegram.api_url deve começar com http:// ou https://: {:?}", api_url));
+        }
+
+        problems.extend(self.validate_data_dir());
+
+        let endereco = &self.metricas.endereco;
+        if !endereco.is_empty() && endereco.parse::<std::net::SocketAddr>().is_err() {
+            problems.push(format!("metricas.endereco deve ser host:porta, como 127.0.0.1:9090: {:?}", endereco));
+        }
+
+        let conversas = &self.conversas;
+        if conversas.expiracao_minutos > MAX_EXPIRACAO_MINUTOS {
+            problems.push(format!(
+                "conversas.expiracao_minutos deve ser no máximo {} (uma semana): {}",
+                MAX_EXPIRACAO_MINUTOS, conversas.expiracao_minutos
+            ));
+        }
+        if conversas.rascunho_dias > MAX_RASCUNHO_DIAS {
+            problems.push(format!("conversas.rascunho_dias deve ser no máximo {}: {}", MAX_RASCUNHO_DIAS, conversas.rascunho_dias));
+        }
+        if conversas.expiracao_minutos > 0 && conversas.lembrete_minutos >= conversas.expiracao_minutos {
+            problems.push(format!(
+                "conversas.lembrete_minutos ({}) deve ser menor que conversas.expiracao_minutos ({})",
+                conversas.lembrete_minutos, conversas.expiracao_minutos
+            ));
+        }
+
+        let limites = &self.limites;
+        if limites.tamanho_maximo_mb == 0 || limites.arquivos_por_entrega == 0 || limites.arquivos_por_jogador == 0 {
+            problems.push("limites.tamanho_maximo_mb, arquivos_por_entrega e arquivos_por_jogador devem ser maiores que zero".to_string());
+        }
+        if limites.tamanho_maximo_mb > MAX_TAMANHO_MB {
+            problems.push(format!("limites.tamanho_maximo_mb deve ser no máximo {}: {}", MAX_TAMANHO_MB, limites.tamanho_maximo_mb));
+        }
+
+        for (nome, id) in self.grupos.all() {
+            if id == 0 {
+                problems.push(format!("grupo {} não configurado (grupos.{} ou {}_GROUP_ID)", nome, nome, nome.to_uppercase()));
+            }
+        }
+        let ids: Vec<i64> = self.grupos.all().iter().map(|(_, id)| *id).filter(|id| *id != 0).collect();
+        if ids.iter().enumerate().any(|(i, id)| ids[..i].contains(id)) {
+            problems.push("dois grupos configurados com o mesmo ID".to_string());
+        }
+
+        problems
+    }
+
+    fn validate_data_dir(&self) -> Vec<String> {
+        if self.data_dir.is_dir() {
+            Vec::new()
+        } else {
+            vec![format!("data_dir não é um diretório: {}", self.data_dir.display())]
+        }
+    }
+
+    // URL dos métodos da API: {base}/bot{token}
+    pub fn bot_api_url(&self) -> String {
+        format!("{}/bot{}", self.api_base(), self.telegram.token)
+    }
+
+    // URL de download de um arquivo devolvido pelo getFile: {base}/file/bot{token}/{caminho}
+    pub fn file_download_url(&self, file_path: &str) -> String {
+        format!("{}/file/bot{}/{}", self.api_base(), self.telegram.token, file_path)
+    }
+
+    fn api_base(&self) -> &str {
+        self.telegram.api_url.trim_end_matches('/')
+    }
+
+    pub fn is_admin_group(&self, chat_id: i64) -> bool {
+        chat_id == self.grupos.admin
+    }
+
+    pub fn team_group_id(&self, time: &str) -> Option<i64> {
+        let id = match time {
+            "shu" => self.grupos.shu,
+            "wei" => self.grupos.wei,
+            "wu" => self.grupos.wu,
+            _ => 0,
+        };
+        Some(id).filter(|id| *id != 0)
+    }
+
+    pub fn team_emoji(&self, time: &str) -> Option<&str> {
+        let emoji = match time {
+            "shu" => self.emojis.shu.as_str(),
+            "wei" => self.emojis.wei.as_str(),
+            "wu" => self.emojis.wu.as_str(),
+            _ => "",
+        };
+        Some(emoji).filter(|emoji| !emoji.is_empty())
+    }
+}
+
+// Lê um número de uma variável de ambiente, se ela estiver definida
+fn env_number<T: std::str::FromStr>(var: &str, target: &mut T, problems: &mut Vec<String>) {
+    if let Ok(value) = env::var(var) {
+        match value.trim().parse() {
+            Ok(n) => *target = n,
+            Err(_) => problems.push(format!("{} deve ser um número inteiro: {:?}", var, value)),
+        }
+    }
+}
+
+impl GruposConfig {
+    pub fn all(&self) -> [(&'static str, i64); 4] {
+        [("admin", self.admin), ("shu", self.shu), ("wei", self.wei), ("wu", self.wu)]
+    }
+}
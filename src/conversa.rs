@@ -0,0 +1,394 @@
+// Conversas em várias etapas (inscrição, entrega de missão...) como máquinas de estado tipadas.
+// Cada fluxo implementa Wizard; o motor aqui cuida da trava do usuário, do /cancelar e da limpeza
+use chrono::Utc;
+use frankenstein::types::Message;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::future::Future;
+use tokio::time::{interval, Duration};
+use tracing::{error, info, warn};
+
+use crate::bot::BotContext;
+use crate::domain::{MissaoCumprida, Prova};
+use crate::error::BotError;
+use crate::handlers::send_message;
+
+// De quanto em quanto tempo as conversas ociosas e os rascunhos vencidos são verificados
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+// Conversa em andamento de um usuário. É gravada em estados.json no encerramento
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "conversa", rename_all = "snake_case")]
+pub enum UserState {
+    Inscricao(Inscricao),
+    Entrega(EntregaConversa),
+    Recurso(RecursoConversa),
+}
+
+// Conversa de um usuário com o momento da última atividade, usado para a expiração
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Sessao {
+    pub conversa: UserState,
+    // Segundos Unix
+    pub atividade: i64,
+    #[serde(default)]
+    pub lembrete_enviado: bool,
+    // Uma mensagem do usuário está sendo tratada. Só conduzir mexe na conversa até ela terminar: a
+    // etapa pode já ter registrado a entrega, e a varredura guardaria as mesmas provas num rascunho
+    #[serde(skip)]
+    pub em_uso: bool,
+}
+
+impl Sessao {
+    pub fn nova(conversa: UserState) -> Self {
+        Sessao {
+            conversa,
+            atividade: Utc::now().timestamp(),
+            lembrete_enviado: false,
+            em_uso: false,
+        }
+    }
+}
+
+// Provas de uma conversa expirada, guardadas até o usuário retomá-la ou o prazo acabar
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Rascunho {
+    pub conversa: UserState,
+    // Segundos Unix
+    pub salvo_em: i64,
+}
+
+// Inscrição no evento: só falta o "sim"
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "etapa", rename_all = "snake_case")]
+pub enum Inscricao {
+    AguardandoConfirmacao,
+}
+
+// Entrega de missão: escolha do time e da missão, depois as provas até o /entregar
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "etapa", rename_all = "snake_case")]
+pub enum EntregaConversa {
+    AguardandoTime,
+    AguardandoMissao { time: String },
+    AguardandoEntregas(Coleta),
+}
+
+// Provas reunidas na última etapa da entrega
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Coleta {
+    pub time: String,
+    // Ausente nos rascunhos de antes de a conversa perguntar pela missão
+    #[serde(default)]
+    pub missao: Option<MissaoCumprida>,
+    // Número da entrega pendente que estas provas completam; None numa entrega nova
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub complementa: Option<u32>,
+    pub entregas: Vec<Prova>,
+    // Último álbum (media_group_id) já confirmado, para responder uma vez por álbum
+    #[serde(default)]
+    pub album: Option<String>,
+    // Último álbum que passou do limite de arquivos, para recusá-lo uma vez só
+    #[serde(default)]
+    pub album_recusado: Option<String>,
+    // O jogador já foi avisado de que nenhuma prova traz o link da partida
+    #[serde(default)]
+    pub sem_link_avisado: bool,
+}
+
+// Recurso contra a recusa de uma entrega: o argumento, depois as provas novas até o /enviar
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "etapa", rename_all = "snake_case")]
+pub enum RecursoConversa {
+    AguardandoArgumento { entrega: u32 },
+    AguardandoProvas {
+        entrega: u32,
+        argumento: String,
+        provas: Vec<Prova>,
+        // Último álbum já confirmado, como na entrega
+        #[serde(default)]
+        album: Option<String>,
+    },
+}
+
+// O que acontece com a conversa depois de uma mensagem
+pub enum Passo<S> {
+    // A conversa segue, neste estado (que pode ser o mesmo)
+    Segue(S),
+    // A conversa terminou
+    Fim,
+}
+
+// Um fluxo de conversa. `avancar` roda sem a trava do usuário, já que costuma fazer I/O; o estado
+// devolvido só é gravado se a conversa não tiver mudado nesse meio-tempo (por um /cancelar, por exemplo)
+pub trait Wizard: Sized + Clone + PartialEq + Send + Sync {
+    // Nome do fluxo nos logs
+    const NOME: &'static str;
+
+    fn avancar(self, message: &Message, ctx: &BotContext) -> impl Future<Output = Result<Passo<Self>, BotError>> + Send;
+
+    // Arquivos baixados que pertencem à conversa e devem ser apagados se ela for abandonada
+    fn arquivos(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    // Se a conversa, ao expirar, deve ser guardada como rascunho em vez de descartada
+    fn guardar_rascunho(&self) -> bool {
+        false
+    }
+
+    // Comandos que fazem parte desta etapa; os demais são tratados fora da conversa
+    fn comandos(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    // Lembrete do que se espera nesta etapa, para quem manda um comando que ela não conhece
+    fn dica(&self) -> &'static str;
+}
+
+impl UserState {
+    fn nome(&self) -> &'static str {
+        match self {
+            UserState::Inscricao(_) => Inscricao::NOME,
+            UserState::Entrega(_) => EntregaConversa::NOME,
+            UserState::Recurso(_) => RecursoConversa::NOME,
+        }
+    }
+
+    pub(crate) fn arquivos(&self) -> Vec<String> {
+        match self {
+            UserState::Inscricao(w) => w.arquivos(),
+            UserState::Entrega(w) => w.arquivos(),
+            UserState::Recurso(w) => w.arquivos(),
+        }
+    }
+
+    fn guardar_rascunho(&self) -> bool {
+        match self {
+            UserState::Inscricao(w) => w.guardar_rascunho(),
+            UserState::Entrega(w) => w.guardar_rascunho(),
+            UserState::Recurso(w) => w.guardar_rascunho(),
+        }
+    }
+
+    pub fn aceita_comando(&self, command: &str) -> bool {
+        match self {
+            UserState::Inscricao(w) => w.comandos().contains(&command),
+            UserState::Entrega(w) => w.comandos().contains(&command),
+            UserState::Recurso(w) => w.comandos().contains(&command),
+        }
+    }
+
+    pub fn dica(&self) -> &'static str {
+        match self {
+            UserState::Inscricao(w) => w.dica(),
+            UserState::Entrega(w) => w.dica(),
+            UserState::Recurso(w) => w.dica(),
+        }
+    }
+}
+
+// Cópia da conversa em andamento do usuário, se houver
+pub(crate) async fn atual(user_id: i64, ctx: &BotContext) -> Option<UserState> {
+    let slot = ctx.user_states.get(user_id)?;
+    let sessao = slot.lock().await;
+    sessao.as_ref().map(|sessao| sessao.conversa.clone())
+}
+
+async fn avancar<W: Wizard>(
+    wizard: W,
+    message: &Message,
+    ctx: &BotContext,
+    wrap: fn(W) -> UserState,
+) -> Result<Option<UserState>, BotError> {
+    Ok(match wizard.avancar(message, ctx).await? {
+        Passo::Segue(next) => Some(wrap(next)),
+        Passo::Fim => None,
+    })
+}
+
+// Entrega a mensagem à conversa em andamento do usuário, se houver
+pub(crate) async fn conduzir(user_id: i64, message: &Message, ctx: &BotContext) -> Result<(), BotError> {
+    let Some(slot) = ctx.user_states.get(user_id) else {
+        return Ok(());
+    };
+    let original = slot.lock().await.as_mut().map(|sessao| {
+        sessao.em_uso = true;
+        sessao.conversa.clone()
+    });
+    let Some(original) = original else {
+        return Ok(());
+    };
+
+    let resultado = match original.clone() {
+        UserState::Inscricao(w) => avancar(w, message, ctx, UserState::Inscricao).await,
+        UserState::Entrega(w) => avancar(w, message, ctx, UserState::Entrega).await,
+        UserState::Recurso(w) => avancar(w, message, ctx, UserState::Recurso).await,
+    };
+
+    let mut guard = slot.lock().await;
+    let mesma = guard.as_ref().map(|sessao| &sessao.conversa) == Some(&original);
+    // Num erro a conversa fica como estava, liberada para a próxima mensagem e para a varredura
+    if let (true, Err(_), Some(sessao)) = (mesma, &resultado, guard.as_mut()) {
+        sessao.em_uso = false;
+    }
+    let next = resultado?;
+    if mesma {
+        let fim = next.is_none();
+        *guard = next.map(Sessao::nova);
+        drop(guard);
+        drop(slot);
+        if fim {
+            ctx.user_states.forget(user_id);
+        }
+        return Ok(());
+    }
+    // A conversa mudou (ou expirou) enquanto a mensagem era tratada; o resultado é descartado.
+    // Só os arquivos criados nesta etapa são apagados: os anteriores pertencem a quem ficou com a conversa
+    let mut manter = guard.as_ref().map(|sessao| sessao.conversa.arquivos()).unwrap_or_default();
+    drop(guard);
+    manter.extend(original.arquivos());
+    warn!(conversa = original.nome(), "Conversa alterada durante o tratamento; resultado descartado");
+    if let Some(next) = next {
+        remover_arquivos(ctx, next.arquivos().into_iter().filter(|f| !manter.contains(f)));
+    }
+    Ok(())
+}
+
+// Encerra a conversa do usuário, apagando os arquivos que ela tinha baixado.
+// Devolve false se não havia conversa em andamento
+pub(crate) async fn cancelar(user_id: i64, ctx: &BotContext) -> bool {
+    let Some(slot) = ctx.user_states.get(user_id) else {
+        return false;
+    };
+    let sessao = slot.lock().await.take();
+    drop(slot);
+    ctx.user_states.forget(user_id);
+    let Some(sessao) = sessao else {
+        return false;
+    };
+    info!(conversa = sessao.conversa.nome(), "Conversa cancelada pelo usuário");
+    remover_arquivos(ctx, sessao.conversa.arquivos().into_iter());
+    true
+}
+
+// Se o usuário tem um rascunho, reabre a conversa a partir dele. Devolve a conversa retomada
+pub(crate) async fn retomar_rascunho(user_id: i64, ctx: &BotContext) -> Result<Option<UserState>, BotError> {
+    let Some(rascunho) = ctx.storage.take_rascunho(user_id)? else {
+        return Ok(None);
+    };
+    info!(conversa = rascunho.conversa.nome(), "Rascunho retomado");
+    ctx.user_states.insert(user_id, rascunho.conversa.clone()).await;
+    Ok(Some(rascunho.conversa))
+}
+
+// Tarefa de fundo: lembra quem está parado há muito tempo, encerra as conversas ociosas e
+// apaga os rascunhos vencidos. As conversas acontecem no privado, então o chat é o próprio usuário
+pub(crate) async fn expirar_conversas(ctx: &BotContext) {
+    let mut ticker = interval(SWEEP_INTERVAL);
+    loop {
+        ticker.tick().await;
+        varrer(ctx, Utc::now().timestamp()).await;
+    }
+}
+
+// Uma passada da tarefa de fundo, como se fosse o instante `agora` (segundos Unix)
+pub(crate) async fn varrer(ctx: &BotContext, agora: i64) {
+    let config = &ctx.config.conversas;
+    if config.expiracao_minutos > 0 {
+        let expiracao = config.expiracao_segundos();
+        let lembrete = config.lembrete_segundos();
+        for (user_id, slot) in ctx.user_states.slots() {
+            let mut guard = slot.lock().await;
+            // Uma conversa em uso não está ociosa, e quem a libera é conduzir
+            let Some(sessao) = guard.as_mut().filter(|sessao| !sessao.em_uso) else {
+                continue;
+            };
+            let ocioso = agora - sessao.atividade;
+            if ocioso >= expiracao {
+                let Some(sessao) = guard.take() else {
+                    continue;
+                };
+                drop(guard);
+                expirar(user_id, sessao.conversa, agora, ctx).await;
+            } else if lembrete > 0 && ocioso >= expiracao - lembrete && !sessao.lembrete_enviado {
+                sessao.lembrete_enviado = true;
+                let guardar = sessao.conversa.guardar_rascunho();
+                drop(guard);
+                lembrar(user_id, config.lembrete_minutos, guardar, ctx).await;
+            }
+        }
+    }
+    // Entradas das conversas encerradas aqui ou que escaparam do descarte ao terminar
+    ctx.user_states.forget_idle();
+    apagar_rascunhos_vencidos(ctx, agora);
+}
+
+async fn lembrar(user_id: i64, minutos: u64, guardar: bool, ctx: &BotContext) {
+    let destino = if guardar {
+        "guardarei o que já me enviou como rascunho"
+    } else {
+        "encerrarei nossa conversa"
+    };
+    let text = format!(
+        "Nobre guerreiro, ainda aguardo sua resposta. Se não tiver notícias suas em {} minutos, {}. Use /cancelar se desejar desistir.",
+        minutos, destino
+    );
+    send_message(user_id, &text, ctx).await;
+}
+
+async fn expirar(user_id: i64, conversa: UserState, agora: i64, ctx: &BotContext) {
+    info!(user_id, conversa = conversa.nome(), "Conversa expirada por inatividade");
+    if !conversa.guardar_rascunho() {
+        remover_arquivos(ctx, conversa.arquivos().into_iter());
+        send_message(user_id, "O tempo se esgotou e encerrei nossa conversa. Quando estiver pronto, recomece e estarei aqui.", ctx).await;
+        return;
+    }
+
+    let rascunho = Rascunho {
+        conversa,
+        salvo_em: agora,
+    };
+    let arquivos = rascunho.conversa.arquivos();
+    match ctx.storage.save_rascunho(user_id, rascunho) {
+        Ok(anterior) => {
+            // Um rascunho antigo substituído não será mais retomado; as provas dele podem ir embora
+            if let Some(anterior) = anterior {
+                remover_arquivos(ctx, anterior.conversa.arquivos().into_iter().filter(|f| !arquivos.contains(f)));
+            }
+            let text = format!(
+                "O tempo se esgotou e encerrei sua entrega, mas guardei suas provas por {} dias. Use /entregarmissao para retomá-la de onde parou.",
+                ctx.config.conversas.rascunho_dias
+            );
+            send_message(user_id, &text, ctx).await;
+        }
+        Err(e) => {
+            error!(user_id, error = %e, "Falha ao guardar rascunho");
+            remover_arquivos(ctx, arquivos.into_iter());
+            send_message(user_id, "O tempo se esgotou e encerrei nossa conversa. Quando estiver pronto, recomece e estarei aqui.", ctx).await;
+        }
+    }
+}
+
+fn apagar_rascunhos_vencidos(ctx: &BotContext, agora: i64) {
+    let limite = agora.saturating_sub(ctx.config.conversas.rascunho_segundos());
+    match ctx.storage.take_expired_rascunhos(limite) {
+        Ok(vencidos) => {
+            for rascunho in vencidos {
+                info!(conversa = rascunho.conversa.nome(), "Rascunho vencido apagado");
+                remover_arquivos(ctx, rascunho.conversa.arquivos().into_iter());
+            }
+        }
+        Err(e) => error!(error = %e, "Falha ao apagar rascunhos vencidos"),
+    }
+}
+
+fn remover_arquivos(ctx: &BotContext, arquivos: impl Iterator<Item = String>) {
+    for arquivo in arquivos {
+        let path = ctx.storage.path(&arquivo);
+        if let Err(e) = fs::remove_file(&path) {
+            warn!(path = %path.display(), error = %e, "Falha ao remover arquivo da conversa");
+        }
+    }
+}
+
@@ -0,0 +1,305 @@
+// Tipos do evento, compartilhados pelo bot, pelos subcomandos e por outras ferramentas
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Os três reinos em disputa
+pub const TIMES: [&str; 3] = ["shu", "wei", "wu"];
+
+// Quantidade de naipes de missões do evento
+pub const NAIPES: usize = 22;
+
+// Estrutura para um inscrito no evento
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Inscrito {
+    pub id: u32,
+    pub nome: String,
+    pub user: String,
+    // Conhecidos na primeira entrega do jogador, quando ele diz o seu time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+}
+
+// Estrutura para uma missão
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Missao {
+    pub titulo: String,
+    pub texto: String,
+}
+
+// Estrutura para uma entrega de missão
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Entrega {
+    // Número da entrega, único entre os três times; 0 nas entregas de antes da numeração
+    #[serde(default)]
+    pub id: u32,
+    #[serde(default)]
+    pub status: StatusEntrega,
+    // Segundos Unix; 0 nas entregas mais antigas
+    #[serde(default)]
+    pub entregue_em: i64,
+    pub nome: String,
+    pub user: String,
+    // ID do Telegram de quem entregou; ausente nas entregas mais antigas
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i64>,
+    pub time: String,
+    pub imagens: Vec<String>,
+    #[serde(default)]
+    pub videos: Vec<String>,
+    pub textos: Vec<String>,
+    // Mensagens do álbum enviado ao grupo de administração; a aprovação ou recusa responde a elas
+    #[serde(default)]
+    pub mensagens_admin: Vec<i32>,
+    // Resumo enviado ao grupo de administração, reescrito quando a entrega muda de situação
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub texto_admin: String,
+    // O resumo foi a legenda do primeiro álbum, e não uma mensagem própria
+    #[serde(default)]
+    pub legenda_admin: bool,
+    // Identificação de cada arquivo, para reconhecer a mesma prova em entregas futuras
+    #[serde(default)]
+    pub assinaturas: Vec<Assinatura>,
+    // Ausente nas entregas feitas antes de a conversa perguntar pela missão
+    #[serde(default)]
+    pub missao: Option<MissaoCumprida>,
+    // Links normalizados das partidas citadas nos textos
+    #[serde(default)]
+    pub partidas: Vec<String>,
+    // Resumos de fim de partida encaminhados do bot do jogo
+    #[serde(default)]
+    pub resumos: Vec<String>,
+    // Decisão do administrador, quando a entrega foi aprovada ou recusada
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avaliacao: Option<Avaliacao>,
+    // Recurso do jogador contra a recusa; só um por entrega
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurso: Option<Recurso>,
+    // Administrador que pegou a entrega para avaliar, para que os outros não a avaliem ao mesmo tempo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub responsavel: Option<Reserva>,
+}
+
+// Quem pegou uma entrega da fila e quando
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Reserva {
+    pub admin: String,
+    pub admin_id: i64,
+    // Segundos Unix
+    pub em: i64,
+}
+
+// Quem aprovou ou recusou uma entrega, quando e por quê
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Avaliacao {
+    // Nome do administrador no Telegram
+    pub admin: String,
+    pub admin_id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motivo: Option<String>,
+    // Segundos Unix
+    pub em: i64,
+}
+
+// Recurso contra a recusa de uma entrega, com o argumento e as provas novas do jogador
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Recurso {
+    pub argumento: String,
+    #[serde(default)]
+    pub textos: Vec<String>,
+    // Arquivos de prova, relativos ao data_dir
+    #[serde(default)]
+    pub arquivos: Vec<String>,
+    // Mensagens do recurso no grupo de administração; a primeira traz os botões de decisão
+    #[serde(default)]
+    pub mensagens_admin: Vec<i32>,
+    // A recusa contestada, guardada porque uma reversão substitui a avaliação da entrega
+    pub recusa: Avaliacao,
+    // Segundos Unix
+    pub aberto_em: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decisao: Option<DecisaoRecurso>,
+}
+
+// Decisão final de um administrador sobre um recurso
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DecisaoRecurso {
+    // true se a recusa foi revertida e a entrega aprovada
+    pub revertida: bool,
+    pub admin: String,
+    pub admin_id: i64,
+    // Segundos Unix
+    pub em: i64,
+}
+
+// Situação de uma entrega. Só as pendentes podem ser retiradas ou receber novas provas
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusEntrega {
+    #[default]
+    Pendente,
+    Aprovada,
+    Recusada,
+    // Retirada pelo próprio jogador antes da avaliação
+    Retirada,
+}
+
+impl StatusEntrega {
+    pub fn descricao(self) -> &'static str {
+        match self {
+            StatusEntrega::Pendente => "⏳ aguardando avaliação",
+            StatusEntrega::Aprovada => "✅ aprovada",
+            StatusEntrega::Recusada => "❌ recusada",
+            StatusEntrega::Retirada => "↩️ retirada",
+        }
+    }
+}
+
+// A missão que o jogador diz ter cumprido: naipe (de 1 a NAIPES) e tipo em minúsculas
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MissaoCumprida {
+    pub naipe: usize,
+    pub tipo: String,
+}
+
+impl fmt::Display for MissaoCumprida {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut chars = self.tipo.chars();
+        let tipo: String = match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        };
+        write!(f, "naipe {} ({})", self.naipe, tipo)
+    }
+}
+
+// Estrutura para um naipe de missões
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Naipe {
+    pub pedra: u32,
+    pub papel: u32,
+    pub tesoura: u32,
+}
+
+// Estrutura para o banco de dados de um time
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TimeDB {
+    pub soldados: i32,
+    pub naipes: Vec<Naipe>,
+}
+
+// Uma prova enviada durante a conversa de entrega
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "tipo", rename_all = "snake_case")]
+pub enum Prova {
+    Texto { texto: String },
+    // Resumo de fim de partida encaminhado do bot do jogo
+    Resumo { texto: String, bot: String },
+    // Arquivo baixado para o data_dir; o file_id permite reenviá-lo sem novo upload
+    Arquivo {
+        midia: Midia,
+        file_id: String,
+        #[serde(flatten)]
+        assinatura: Assinatura,
+    },
+}
+
+// Um arquivo de prova baixado e o que o identifica
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Assinatura {
+    // Relativo ao data_dir
+    pub caminho: String,
+    // Igual para o mesmo arquivo do Telegram, mesmo reenviado por outra pessoa
+    #[serde(default)]
+    pub file_unique_id: String,
+    // Hash perceptual das imagens, que sobrevive a recompressão e redimensionamento
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phash: Option<u64>,
+}
+
+// Tipos de arquivo aceitos como prova
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Midia {
+    Foto,
+    // Imagem enviada como arquivo, sem a compressão do Telegram
+    Documento,
+    Video,
+    VideoNota,
+}
+
+impl Midia {
+    pub fn is_image(self) -> bool {
+        matches!(self, Midia::Foto | Midia::Documento)
+    }
+}
+
+impl Prova {
+    pub fn caminho(&self) -> Option<&str> {
+        match self {
+            Prova::Arquivo { assinatura, .. } => Some(&assinatura.caminho),
+            Prova::Texto { .. } | Prova::Resumo { .. } => None,
+        }
+    }
+}
+
+impl Entrega {
+    // Monta a entrega registrada a partir das provas coletadas na conversa
+    pub fn from_provas(nome: String, user: String, time: String, provas: &[Prova]) -> Self {
+        let mut entrega = Entrega {
+            id: 0,
+            status: StatusEntrega::Pendente,
+            entregue_em: 0,
+            nome,
+            user,
+            user_id: None,
+            time,
+            imagens: Vec::new(),
+            videos: Vec::new(),
+            textos: Vec::new(),
+            mensagens_admin: Vec::new(),
+            texto_admin: String::new(),
+            legenda_admin: false,
+            assinaturas: Vec::new(),
+            missao: None,
+            partidas: Vec::new(),
+            resumos: Vec::new(),
+            avaliacao: None,
+            recurso: None,
+            responsavel: None,
+        };
+        for prova in provas {
+            match prova {
+                Prova::Texto { texto } => entrega.textos.push(texto.clone()),
+                Prova::Resumo { texto, .. } => entrega.resumos.push(texto.clone()),
+                Prova::Arquivo { midia, assinatura, .. } => {
+                    if midia.is_image() {
+                        entrega.imagens.push(assinatura.caminho.clone());
+                    } else {
+                        entrega.videos.push(assinatura.caminho.clone());
+                    }
+                    entrega.assinaturas.push(assinatura.clone());
+                }
+            }
+        }
+        entrega
+    }
+
+    // Quantidade de arquivos de prova (imagens e vídeos)
+    pub fn arquivos(&self) -> usize {
+        self.imagens.len() + self.videos.len()
+    }
+
+    // Junta as provas de um complemento enviado depois da entrega
+    pub fn juntar(&mut self, complemento: Entrega) {
+        self.imagens.extend(complemento.imagens);
+        self.videos.extend(complemento.videos);
+        self.textos.extend(complemento.textos);
+        self.resumos.extend(complemento.resumos);
+        self.assinaturas.extend(complemento.assinaturas);
+        self.partidas.extend(complemento.partidas);
+        self.partidas.sort();
+        self.partidas.dedup();
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+use thiserror::Error;
+
+// Erros de acesso aos arquivos de dados
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("falha ao acessar {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("conteúdo inválido em {path}: {source}")]
+    Json {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+// Erros que um handler pode devolver. Cada um tem uma resposta ao usuário e uma entrada de log
+#[derive(Debug, Error)]
+pub enum BotError {
+    #[error("configuração inválida: {0}")]
+    Config(String),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("falha na API do Telegram: {0}")]
+    Telegram(#[from] frankenstein::Error),
+    #[error("entrada inválida: {0}")]
+    Parse(String),
+    // Carrega a própria resposta, já que cada comando restrito explica onde pode ser usado
+    #[error("permissão negada: {0}")]
+    Permission(&'static str),
+    // Pedido recusado, como uma prova acima dos limites ou a alteração de uma entrega já avaliada;
+    // também carrega a própria resposta
+    #[error("pedido recusado: {0}")]
+    Recusa(String),
+}
+
+impl BotError {
+    // Nome curto usado nos logs
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BotError::Config(_) => "config",
+            BotError::Storage(_) => "storage",
+            BotError::Telegram(_) => "telegram",
+            BotError::Parse(_) => "parse",
+            BotError::Permission(_) => "permission",
+            BotError::Recusa(_) => "recusa",
+        }
+    }
+
+    // Resposta enviada ao usuário, na voz de Yuan Shao
+    pub fn user_message(&self) -> &str {
+        match self {
+            BotError::Config(_) => "Meus conselheiros cometeram um erro na organização do acampamento. Os administradores precisam corrigi-lo antes que eu possa atendê-lo.",
+            BotError::Storage(_) => "Houve uma falha em meus arquivos. Peço que tente novamente mais tarde.",
+            BotError::Telegram(_) => "Meus mensageiros falharam em sua tarefa. Tente novamente em instantes, nobre guerreiro.",
+            BotError::Parse(_) => "Não compreendi suas palavras, nobre guerreiro. Verifique o formato e tente novamente.",
+            BotError::Permission(message) => message,
+            BotError::Recusa(message) => message,
+        }
+    }
+}
+
+impl From<reqwest::Error> for BotError {
+    fn from(error: reqwest::Error) -> Self {
+        // Sem a URL, que contém o token do bot
+        BotError::Telegram(frankenstein::Error::HttpReqwest(error.without_url()))
+    }
+}
@@ -0,0 +1,44 @@
+// Textos montados a partir dos dados, usados tanto pelo bot quanto pela linha de comando e pelos
+// relatórios, sem depender do tratamento das mensagens
+use crate::error::BotError;
+use crate::parsing::parse_missoes_emojis;
+use crate::storage::Storage;
+
+// Link para uma mensagem de supergrupo; grupos comuns não têm links de mensagem
+pub fn message_link(chat_id: i64, message_id: i32) -> Option<String> {
+    let interno = chat_id.to_string().strip_prefix("-100")?.to_string();
+    Some(format!("https://t.me/c/{}/{}", interno, message_id))
+}
+
+// Relatório do banco de um time, com os emojis de cada missão; usado pelo bot e pelo subcomando show
+pub fn format_team_db(storage: &Storage, team_name: &str) -> Result<String, BotError> {
+    let missoes_data = storage.read_missoes()?;
+
+    let missoes_text = missoes_data.first().map(|m| m.texto.as_str()).unwrap_or("");
+    let mission_emojis = parse_missoes_emojis(missoes_text);
+
+    let db = storage.read_time_db(team_name)?;
+
+    let mut response = format!("📊 Banco de Dados do Time {} 📊\n\n", team_name.to_uppercase());
+    response.push_str(&format!("Soldados: {}\n\n", db.soldados));
+    response.push_str("Missões por Naipe:\n");
+
+    for (i, naipe) in db.naipes.iter().enumerate() {
+        let naipe_index = i + 1;
+        let emojis_for_naipe = mission_emojis.emojis.get(&naipe_index);
+
+        let pedra_emoji = emojis_for_naipe.and_then(|e| e.get("Pedra")).map_or("🛡", |s| s.as_str());
+        let papel_emoji = emojis_for_naipe.and_then(|e| e.get("Papel")).map_or("📜", |s| s.as_str());
+        let tesoura_emoji = emojis_for_naipe.and_then(|e| e.get("Tesoura")).map_or("✂️", |s| s.as_str());
+
+        response.push_str(&format!(
+            "\nNaipe {}: {} Pedra: {} | {} Papel: {} | {} Tesoura: {}\n",
+            naipe_index,
+            pedra_emoji, naipe.pedra,
+            papel_emoji, naipe.papel,
+            tesoura_emoji, naipe.tesoura
+        ));
+    }
+
+    Ok(response)
+}
@@ -0,0 +1,2039 @@
+// Tratamento das mensagens recebidas: comandos, etapas da conversa de entrega e comandos de admin
+use chrono::Utc;
+use frankenstein::client_reqwest::Bot;
+use frankenstein::input_file::FileUpload;
+use frankenstein::input_media::{InputMediaDocument, InputMediaPhoto, InputMediaVideo, MediaGroupInputMedia};
+use frankenstein::methods::{
+    AnswerCallbackQueryParams, EditMessageCaptionParams, EditMessageTextParams, GetChatParams, GetFileParams, SendMediaGroupParams, SendMessageParams,
+    SendVideoNoteParams, SetMyCommandsParams,
+};
+use frankenstein::types::{
+    BotCommand, CallbackQuery, ChatType, InlineKeyboardButton, InlineKeyboardMarkup, MaybeInaccessibleMessage, Message,
+    MessageOrigin, ReplyMarkup, ReplyParameters, User,
+};
+use frankenstein::response::MethodResponse;
+use frankenstein::AsyncTelegramApi;
+use regex::Regex;
+use std::fs;
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use tokio::time::{Duration, Instant};
+use tracing::{debug, error, info, info_span, warn};
+
+use crate::bot::BotContext;
+use crate::conversa::{self, Coleta, EntregaConversa, Inscricao, Passo, RecursoConversa, UserState, Wizard};
+use crate::domain::{Assinatura, Avaliacao, DecisaoRecurso, Entrega, Midia, MissaoCumprida, Prova, Recurso, Reserva, StatusEntrega, TIMES};
+use crate::error::{BotError, StorageError};
+use crate::format::{format_team_db, message_link};
+use crate::parsing::{
+    has_malformed_link, parse_links_partida, parse_missao_cumprida, parse_missao_descricao,
+};
+use crate::provas::{self, Anterior, Repetida};
+use crate::resumo::{self, parse_resumo};
+use crate::revisao;
+
+// Span com os campos que identificam uma atualização em todos os logs gerados durante o seu tratamento
+pub(crate) fn update_span(update_id: u32, message: &Message) -> tracing::Span {
+    let command = command_name(message);
+    info_span!(
+        "update",
+        update_id,
+        chat_id = message.chat.id,
+        user_id = message.from.as_ref().map_or(0, |u| u.id),
+        command,
+    )
+}
+
+// Comando no início do texto, sem argumentos nem @nome_do_bot
+fn command_name(message: &Message) -> Option<&str> {
+    message.text.as_deref()
+        .filter(|text| text.starts_with('/'))
+        .and_then(|text| text.split_whitespace().next())
+        .and_then(|command| command.split('@').next())
+}
+
+// "/comando@outro_bot" é para outro bot do grupo. Sem o nome deste bot, como na reprodução, o sufixo não
+// é conferido
+fn addressed_to_other_bot(message: &Message, ctx: &BotContext) -> bool {
+    let destino = message.text.as_deref()
+        .filter(|text| text.starts_with('/'))
+        .and_then(|text| text.split_whitespace().next())
+        .and_then(|command| command.split_once('@'))
+        .map(|(_, bot)| bot);
+    match (destino, ctx.username.get()) {
+        (Some(destino), Some(username)) => !destino.eq_ignore_ascii_case(username),
+        _ => false,
+    }
+}
+
+pub async fn process_message(message: Message, ctx: &BotContext) {
+    debug!(chat_title = message.chat.title.as_deref().unwrap_or("Chat Privado"), "Nova mensagem recebida");
+    ctx.metrics.update_processed();
+    if addressed_to_other_bot(&message, ctx) {
+        debug!("Comando para outro bot; ignorado");
+        return;
+    }
+    if let Some(command) = command_name(&message) {
+        // Só comandos conhecidos viram rótulo, para que a métrica não cresça sem limite
+        ctx.metrics.command(if is_known_command(command) { command } else { "desconhecido" });
+    }
+
+    let user_id = message.from.as_ref().map_or(0, |u| u.id as i64);
+    let command = command_name(&message);
+
+    // As conversas acontecem no privado; o que o usuário escreve nos grupos não entra nelas
+    let conversa = if message.chat.type_field == ChatType::Private {
+        conversa::atual(user_id, ctx).await
+    } else {
+        None
+    };
+
+    let result = if command == Some("/cancelar") && message.chat.type_field == ChatType::Private {
+        cancel_conversation(user_id, message.chat.id, ctx).await;
+        Ok(())
+    } else if let Some(conversa) = conversa {
+        match (command, &message.text) {
+            // Comandos comuns continuam funcionando no meio de uma conversa, sem entrar nela
+            (Some(command), Some(text)) if !conversa.aceita_comando(command) && GLOBAL_COMMANDS.contains(&command) => {
+                handle_command_logic(text, &message, ctx).await
+            }
+            (Some(command), _) if !conversa.aceita_comando(command) => {
+                send_message(message.chat.id, conversa.dica(), ctx).await;
+                Ok(())
+            }
+            _ => conversa::conduzir(user_id, &message, ctx).await,
+        }
+    } else if let Some(text) = &message.text {
+        handle_command_logic(text, &message, ctx).await
+    } else {
+        Ok(())
+    };
+
+    if let Err(error) = result {
+        match error {
+            BotError::Parse(_) | BotError::Permission(_) | BotError::Recusa(_) => warn!(kind = error.kind(), error = %error, "Mensagem recusada"),
+            _ => error!(kind = error.kind(), error = %error, "Falha ao processar mensagem"),
+        }
+        send_message(message.chat.id, error.user_message(), ctx).await;
+    }
+}
+
+// /cancelar vale em qualquer conversa e em qualquer etapa, mas só no privado, onde as conversas acontecem
+async fn cancel_conversation(user_id: i64, chat_id: i64, ctx: &BotContext) {
+    if conversa::cancelar(user_id, ctx).await {
+        send_message(chat_id, "Como desejar. Deixemos este assunto de lado; quando estiver pronto, estarei aqui.", ctx).await;
+    } else {
+        send_message(chat_id, "Não há nada em andamento para cancelar, nobre guerreiro.", ctx).await;
+    }
+}
+
+fn sender(message: &Message) -> Result<&User, BotError> {
+    message.from.as_deref().ok_or_else(|| BotError::Parse("mensagem sem remetente".to_string()))
+}
+
+// Nome como aparece para os outros jogadores, onde fica o emoji do time
+fn display_name(user: &User) -> String {
+    match &user.last_name {
+        Some(last_name) => format!("{} {}", user.first_name, last_name),
+        None => user.first_name.clone(),
+    }
+}
+
+impl Wizard for Inscricao {
+    const NOME: &'static str = "inscricao";
+
+    async fn avancar(self, message: &Message, ctx: &BotContext) -> Result<Passo<Self>, BotError> {
+        let chat_id = message.chat.id;
+        match self {
+            Inscricao::AguardandoConfirmacao => {
+                let user = sender(message)?;
+                let text = message.text.as_deref().unwrap_or("").to_lowercase();
+                if ["sim", "s", "ss"].contains(&text.as_str()) {
+                    let nome = user.first_name.clone();
+                    let username = user.username.as_deref().unwrap_or("").to_string();
+                    ctx.storage.add_inscrito(nome, username)?;
+                    send_message(chat_id, "Sua lealdade foi registrada! Você agora é um de meus nobres seguidores. Juntos, alcançaremos a glória!", ctx).await;
+                } else {
+                    send_message(chat_id, "Sua hesitação é compreensível, mas a glória não espera por ninguém. Quando estiver pronto para se juntar a mim, estarei aqui.", ctx).await;
+                }
+                Ok(Passo::Fim)
+            }
+        }
+    }
+
+    fn dica(&self) -> &'static str {
+        "Ainda aguardo sua resposta: diga 'sim' para jurar lealdade, ou use /cancelar."
+    }
+}
+
+impl Wizard for EntregaConversa {
+    const NOME: &'static str = "entrega";
+
+    async fn avancar(self, message: &Message, ctx: &BotContext) -> Result<Passo<Self>, BotError> {
+        let chat_id = message.chat.id;
+        match self {
+            EntregaConversa::AguardandoTime => {
+                let time = message.text.as_deref().unwrap_or("").to_lowercase();
+                if TIMES.contains(&time.as_str()) {
+                    let user = sender(message)?;
+                    if let Err(e) = ctx.storage.vincular_inscrito(user.username.as_deref().unwrap_or(""), user.id as i64, &time) {
+                        warn!(error = %e, "Falha ao registrar o time do inscrito");
+                    }
+                    if let Some(emoji) = ctx.config.team_emoji(&time).filter(|emoji| !display_name(user).contains(emoji)) {
+                        let aviso = format!(
+                            "Guerreiro, não vejo o emoji de {} ({}) em seu nome. As regras exigem jogar com o emoji do time para que a missão conte; ajuste seu nome antes das próximas partidas.",
+                            time.to_uppercase(),
+                            emoji
+                        );
+                        send_message(chat_id, &aviso, ctx).await;
+                    }
+                    send_message(chat_id, "Excelente. Agora diga-me qual missão você cumpriu: o naipe e o tipo, como em \"3 pedra\".", ctx).await;
+                    Ok(Passo::Segue(EntregaConversa::AguardandoMissao { time }))
+                } else {
+                    send_message(chat_id, "Guerreiro, essa casa não figura entre as grandes. Escolha entre Shu, Wei ou Wu para que eu possa registrar seus feitos corretamente.", ctx).await;
+                    Ok(Passo::Segue(EntregaConversa::AguardandoTime))
+                }
+            }
+            EntregaConversa::AguardandoMissao { time } => {
+                let Some(missao) = message.text.as_deref().and_then(parse_missao_cumprida) else {
+                    send_message(chat_id, "Não reconheço essa missão, guerreiro. Diga o naipe, de 1 a 22, e o tipo — pedra, papel ou tesoura —, como em \"3 pedra\".", ctx).await;
+                    return Ok(Passo::Segue(EntregaConversa::AguardandoMissao { time }));
+                };
+                let text = format!(
+                    "Missão do {} anotada. Agora, apresente-me as provas de seus feitos: o print da missão e o link do final da partida. Quando terminar, use o comando /entregar para que eu possa avaliar sua bravura.",
+                    missao
+                );
+                send_message(chat_id, &text, ctx).await;
+                Ok(Passo::Segue(EntregaConversa::AguardandoEntregas(Coleta {
+                    time,
+                    missao: Some(missao),
+                    complementa: None,
+                    entregas: Vec::new(),
+                    album: None,
+                    album_recusado: None,
+                    sem_link_avisado: false,
+                })))
+            }
+            EntregaConversa::AguardandoEntregas(coleta) => coletar(coleta, message, ctx).await,
+        }
+    }
+
+    fn arquivos(&self) -> Vec<String> {
+        match self {
+            EntregaConversa::AguardandoTime | EntregaConversa::AguardandoMissao { .. } => Vec::new(),
+            EntregaConversa::AguardandoEntregas(coleta) => {
+                coleta.entregas.iter().filter_map(Prova::caminho).map(str::to_string).collect()
+            }
+        }
+    }
+
+    fn guardar_rascunho(&self) -> bool {
+        matches!(self, EntregaConversa::AguardandoEntregas(coleta) if !coleta.entregas.is_empty())
+    }
+
+    fn comandos(&self) -> &'static [&'static str] {
+        match self {
+            EntregaConversa::AguardandoTime | EntregaConversa::AguardandoMissao { .. } => &[],
+            EntregaConversa::AguardandoEntregas(_) => &["/entregar"],
+        }
+    }
+
+    fn dica(&self) -> &'static str {
+        match self {
+            EntregaConversa::AguardandoTime => "Antes de tudo, diga-me sua casa: Shu, Wei ou Wu. Se desistiu, use /cancelar.",
+            EntregaConversa::AguardandoMissao { .. } => "Diga-me a missão que cumpriu, como em \"3 pedra\". Se desistiu, use /cancelar.",
+            EntregaConversa::AguardandoEntregas(_) => "Não conheço essa ordem, guerreiro. Envie suas provas, use /entregar para finalizar ou /cancelar para desistir.",
+        }
+    }
+}
+
+// Última etapa da entrega: textos e arquivos até o /entregar
+async fn coletar(mut coleta: Coleta, message: &Message, ctx: &BotContext) -> Result<Passo<EntregaConversa>, BotError> {
+    let chat_id = message.chat.id;
+    if let Some(text) = &message.text {
+        if text == "/entregar" {
+            return match coleta.complementa {
+                Some(id) => complementar(id, coleta, message, ctx).await,
+                None => entregar(coleta, message, ctx).await,
+            };
+        }
+        if let Some(bot) = forwarded_from_bot(message).filter(|_| parse_resumo(text).is_some()) {
+            coleta.entregas.push(Prova::Resumo { texto: text.clone(), bot });
+            send_message(chat_id, "Recebi o resumo da partida; meus conselheiros o conferirão com a missão. Envie mais provas ou use /entregar para finalizar.", ctx).await;
+            return Ok(Passo::Segue(EntregaConversa::AguardandoEntregas(coleta)));
+        }
+        if let Some(recusa) = link_refusal(text, &coleta, ctx)? {
+            send_message(chat_id, &recusa, ctx).await;
+            return Ok(Passo::Segue(EntregaConversa::AguardandoEntregas(coleta)));
+        }
+        let partidas = parse_links_partida(text);
+        coleta.entregas.push(Prova::Texto { texto: text.clone() });
+        let ack = if partidas.is_empty() {
+            "Registrado. Envie mais provas ou use /entregar para finalizar."
+        } else {
+            "Link da partida registrado. Envie mais provas ou use /entregar para finalizar."
+        };
+        send_message(chat_id, ack, ctx).await;
+        return Ok(Passo::Segue(EntregaConversa::AguardandoEntregas(coleta)));
+    }
+
+    let (midia, file_id) = match attachment(message) {
+        Ok(attachment) => attachment,
+        Err(recusa) => {
+            send_message(chat_id, recusa, ctx).await;
+            return Ok(Passo::Segue(EntregaConversa::AguardandoEntregas(coleta)));
+        }
+    };
+    let user_id = sender(message)?.id as i64;
+    if let Some(recusa) = attachment_limit(&coleta, user_id, ctx)? {
+        // Num álbum acima do limite, só o primeiro arquivo recusado recebe resposta
+        let same_album = message.media_group_id.is_some() && message.media_group_id == coleta.album_recusado;
+        if !same_album {
+            send_message(chat_id, &recusa, ctx).await;
+        }
+        coleta.album_recusado = message.media_group_id.clone();
+        return Ok(Passo::Segue(EntregaConversa::AguardandoEntregas(coleta)));
+    }
+    // A legenda passa pela mesma conferência de links de um texto; recusada, o arquivo fica sem ela
+    let legenda = message.caption.as_ref().filter(|c| !c.trim().is_empty());
+    let legenda_recusada = match legenda {
+        Some(caption) => link_refusal(caption, &coleta, ctx)?,
+        None => None,
+    };
+    if let Some(prova) = baixar_prova(midia, file_id, &coleta.time, message, ctx).await {
+        coleta.entregas.push(prova);
+        match (legenda, legenda_recusada) {
+            (_, Some(recusa)) => send_message(chat_id, &format!("Guardei o arquivo, mas não a legenda. {}", recusa), ctx).await,
+            (Some(caption), None) => coleta.entregas.push(Prova::Texto { texto: caption.clone() }),
+            (None, None) => {}
+        }
+        // Cada foto de um álbum chega numa mensagem própria; só a primeira recebe resposta
+        let same_album = message.media_group_id.is_some() && message.media_group_id == coleta.album;
+        if !same_album {
+            let ack = match (&message.media_group_id, midia) {
+                (Some(_), _) => "Seu álbum foi recebido. Envie mais provas ou use /entregar.",
+                (None, Midia::Video | Midia::VideoNota) => "Seu vídeo foi recebido. Envie mais ou use /entregar.",
+                (None, _) => "Sua imagem foi recebida. Envie mais ou use /entregar.",
+            };
+            send_message(chat_id, ack, ctx).await;
+        }
+        coleta.album = message.media_group_id.clone();
+    }
+    Ok(Passo::Segue(EntregaConversa::AguardandoEntregas(coleta)))
+}
+
+// Baixa o arquivo de uma mensagem como prova. Se não der, o usuário recebe o motivo e volta None
+async fn baixar_prova(midia: Midia, file_id: &str, time: &str, message: &Message, ctx: &BotContext) -> Option<Prova> {
+    let chat_id = message.chat.id;
+    let user_id = message.from.as_ref().map_or(0, |u| u.id as i64);
+    match download_file(ctx, file_id, midia, time, user_id).await {
+        Ok(assinatura) => Some(Prova::Arquivo { midia, file_id: file_id.to_string(), assinatura }),
+        Err(BotError::Recusa(recusa)) => {
+            info!(recusa = %recusa, "Arquivo recusado");
+            send_message(chat_id, &recusa, ctx).await;
+            None
+        }
+        Err(e) => {
+            if let BotError::Telegram(_) = e {
+                ctx.metrics.telegram_error("getFile");
+            }
+            error!(error = %e, "Falha ao baixar arquivo");
+            send_message(chat_id, "Houve uma falha ao receber seu arquivo. Por favor, tente novamente.", ctx).await;
+            None
+        }
+    }
+}
+
+// Recusa um texto cujo link não aponta para uma mensagem ou traz uma partida já apresentada para a missão
+fn link_refusal(text: &str, coleta: &Coleta, ctx: &BotContext) -> Result<Option<String>, BotError> {
+    let partidas = parse_links_partida(text);
+    if partidas.is_empty() && has_malformed_link(text) {
+        return Ok(Some("Esse link não me leva ao fim da partida, guerreiro. Copie o link da mensagem final do jogo, como https://t.me/grupo/12345, e envie-o novamente.".to_string()));
+    }
+    match ctx.storage.conferir_partidas(&coleta.time, coleta.missao.as_ref(), &partidas, coleta.complementa) {
+        Ok(()) => Ok(None),
+        Err(BotError::Recusa(recusa)) => Ok(Some(recusa)),
+        Err(e) => Err(e),
+    }
+}
+
+// Recusa em nome dos limites de arquivos por entrega e por jogador, antes de qualquer download
+fn attachment_limit(coleta: &Coleta, user_id: i64, ctx: &BotContext) -> Result<Option<String>, BotError> {
+    let limites = &ctx.config.limites;
+    let novos = coleta.entregas.iter().filter(|prova| prova.caminho().is_some()).count();
+    // Num complemento, os arquivos já registrados na entrega também contam
+    let registrados = match coleta.complementa {
+        Some(id) => ctx.storage.buscar_entrega(id)?.map_or(0, |entrega| entrega.arquivos()),
+        None => 0,
+    };
+    let nesta_entrega = registrados + novos;
+    if nesta_entrega >= limites.arquivos_por_entrega {
+        return Ok(Some(format!(
+            "Já recebi {} arquivos nesta entrega, o máximo que aceito de uma vez. Use /entregar para finalizá-la; outras provas podem seguir numa nova entrega.",
+            nesta_entrega
+        )));
+    }
+    if ctx.storage.arquivos_do_jogador(user_id)? + novos >= limites.arquivos_por_jogador {
+        return Ok(Some(format!(
+            "Guerreiro, você já me apresentou {} arquivos neste evento, o máximo que meus arquivistas guardam por jogador. Fale com os administradores se precisar enviar mais.",
+            limites.arquivos_por_jogador
+        )));
+    }
+    Ok(None)
+}
+
+async fn entregar(mut coleta: Coleta, message: &Message, ctx: &BotContext) -> Result<Passo<EntregaConversa>, BotError> {
+    let chat_id = message.chat.id;
+    let user = sender(message)?;
+    let mut new_entrega = Entrega::from_provas(
+        user.first_name.clone(),
+        user.username.as_deref().unwrap_or("").to_string(),
+        coleta.time.clone(),
+        &coleta.entregas,
+    );
+    new_entrega.user_id = Some(user.id as i64);
+    new_entrega.entregue_em = Utc::now().timestamp();
+    new_entrega.missao = coleta.missao.clone();
+    new_entrega.partidas = new_entrega.textos.iter().flat_map(|texto| parse_links_partida(texto)).collect();
+    new_entrega.partidas.sort();
+    new_entrega.partidas.dedup();
+
+    // As regras pedem o link; avisa uma vez e aceita um segundo /entregar sem ele
+    if new_entrega.partidas.is_empty() && !coleta.sem_link_avisado {
+        send_message(chat_id, "Falta o link do final da partida, guerreiro; as regras pedem o print e o link. Envie-o agora, ou use /entregar de novo para entregar assim mesmo.", ctx).await;
+        coleta.sem_link_avisado = true;
+        return Ok(Passo::Segue(EntregaConversa::AguardandoEntregas(coleta)));
+    }
+    let repetidas = provas::procurar_repetidas(&ctx.storage, &new_entrega.assinaturas).unwrap_or_else(|e| {
+        warn!(error = %e, "Falha ao procurar provas repetidas");
+        Vec::new()
+    });
+    // Registrada antes do envio, para que o resumo já leve o número da entrega. Um colega pode ter entregue
+    // a mesma partida enquanto estas provas eram reunidas; a recusa vem do registro, sob a trava
+    new_entrega.id = match ctx.storage.append_entrega(new_entrega.clone()) {
+        Ok(id) => id,
+        Err(BotError::Recusa(recusa)) => {
+            send_message(chat_id, &recusa, ctx).await;
+            return Ok(Passo::Segue(EntregaConversa::AguardandoEntregas(coleta)));
+        }
+        Err(e) => return Err(e),
+    };
+    ctx.metrics.delivery(&new_entrega.time);
+
+    let admin_group_id = ctx.config.grupos.admin;
+    let titulo = format!(
+        "Nova entrega Nº {} de {} (@{}) para o time {}:",
+        new_entrega.id,
+        new_entrega.nome,
+        new_entrega.user,
+        new_entrega.time.to_uppercase()
+    );
+    let texto = admin_summary(admin_group_id, &titulo, &new_entrega, &repetidas, ctx);
+    let (mensagens, legenda) = send_to_admin(admin_group_id, &texto, &coleta.entregas, None, ctx).await;
+    ctx.storage.update_entrega(new_entrega.id, |entrega| {
+        entrega.mensagens_admin = mensagens;
+        entrega.texto_admin = texto;
+        entrega.legenda_admin = legenda;
+        Ok(())
+    })?;
+
+    let text = format!(
+        "Seus feitos foram registrados como a entrega Nº {} e enviados para avaliação. Acompanhe-a com /minhasentregas; sua bravura será reconhecida, nobre guerreiro!",
+        new_entrega.id
+    );
+    send_message(chat_id, &text, ctx).await;
+    Ok(Passo::Fim)
+}
+
+// Fim de um /adicionarprovas: junta as novas provas à entrega pendente e as envia ao grupo de
+// administração, no fio da entrega original
+async fn complementar(id: u32, coleta: Coleta, message: &Message, ctx: &BotContext) -> Result<Passo<EntregaConversa>, BotError> {
+    let chat_id = message.chat.id;
+    let user = sender(message)?;
+    if coleta.entregas.is_empty() {
+        send_message(chat_id, "Ainda não recebi nenhuma prova nova, guerreiro. Envie-as antes de usar /entregar, ou use /cancelar para desistir.", ctx).await;
+        return Ok(Passo::Segue(EntregaConversa::AguardandoEntregas(coleta)));
+    }
+    // A entrega pode ter sido avaliada enquanto as provas eram reunidas
+    let entrega = match entrega_pendente(id, user.id as i64, ctx) {
+        Ok(entrega) => entrega,
+        Err(BotError::Recusa(recusa)) => {
+            send_message(chat_id, &format!("{} Use /cancelar para descartar estas provas.", recusa), ctx).await;
+            return Ok(Passo::Segue(EntregaConversa::AguardandoEntregas(coleta)));
+        }
+        Err(e) => return Err(e),
+    };
+
+    let mut complemento = Entrega::from_provas(entrega.nome.clone(), entrega.user.clone(), entrega.time.clone(), &coleta.entregas);
+    complemento.id = entrega.id;
+    complemento.missao = entrega.missao.clone();
+    complemento.partidas = complemento.textos.iter().flat_map(|texto| parse_links_partida(texto)).collect();
+    let repetidas = provas::procurar_repetidas(&ctx.storage, &complemento.assinaturas).unwrap_or_else(|e| {
+        warn!(error = %e, "Falha ao procurar provas repetidas");
+        Vec::new()
+    });
+
+    // O aviso de falta de link considera também os links já entregues
+    let mut resumo = complemento.clone();
+    resumo.partidas.extend(entrega.partidas.iter().cloned());
+    let admin_group_id = ctx.config.grupos.admin;
+    let titulo = format!(
+        "📎 Novas provas para a entrega Nº {} de {} (@{}) para o time {}:",
+        entrega.id,
+        entrega.nome,
+        entrega.user,
+        entrega.time.to_uppercase()
+    );
+    let texto = admin_summary(admin_group_id, &titulo, &resumo, &repetidas, ctx);
+
+    // As provas são juntadas antes de irem aos admins: uma avaliação feita logo depois já as encontra na
+    // entrega. A situação e as partidas são conferidas de novo sob a trava
+    let juntada = ctx.storage.update_entrega(id, |entrega| {
+        if entrega.status != StatusEntrega::Pendente {
+            return Err(BotError::Recusa(format!("A entrega Nº {} já está {}; as novas provas não foram juntadas a ela.", id, entrega.status.descricao())));
+        }
+        ctx.storage.conferir_partidas(&entrega.time, entrega.missao.as_ref(), &complemento.partidas, Some(id))?;
+        entrega.juntar(complemento);
+        Ok(())
+    });
+    match juntada {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(BotError::Recusa(format!("Não encontro a entrega Nº {}.", id))),
+        Err(BotError::Recusa(recusa)) => {
+            send_message(chat_id, &format!("{} Use /cancelar para descartá-las.", recusa), ctx).await;
+            return Ok(Passo::Segue(EntregaConversa::AguardandoEntregas(coleta)));
+        }
+        Err(e) => return Err(e),
+    }
+
+    let (mensagens, _) = send_to_admin(admin_group_id, &texto, &coleta.entregas, entrega.mensagens_admin.first().copied(), ctx).await;
+    let link = mensagens.first().and_then(|id| message_link(admin_group_id, *id)).map(|link| format!(": {}", link)).unwrap_or_default();
+    let atualizada = ctx.storage.update_entrega(id, |entrega| {
+        entrega.mensagens_admin.extend(mensagens);
+        entrega.texto_admin.push_str(&format!("\n\n📎 Provas adicionadas depois{}", link));
+        Ok(())
+    })?;
+    if let Some(entrega) = atualizada {
+        update_admin_summary(&entrega, ctx).await;
+    }
+
+    let text = format!("Juntei as novas provas à entrega Nº {} e as enviei aos meus conselheiros. Sua bravura será reconhecida, nobre guerreiro!", id);
+    send_message(chat_id, &text, ctx).await;
+    Ok(Passo::Fim)
+}
+
+// A entrega de número `id`, se for do jogador e ainda aguardar avaliação; a recusa explica o contrário
+fn entrega_pendente(id: u32, user_id: i64, ctx: &BotContext) -> Result<Entrega, BotError> {
+    let entrega = ctx.storage.buscar_entrega(id)?.filter(|entrega| id > 0 && entrega.user_id == Some(user_id));
+    let entrega = entrega.ok_or_else(|| {
+        BotError::Recusa(format!("Não encontro a entrega Nº {} entre as suas, guerreiro. Confira os números em /minhasentregas.", id))
+    })?;
+    if entrega.status != StatusEntrega::Pendente {
+        return Err(BotError::Recusa(format!(
+            "A entrega Nº {} já está {}; só as que aguardam avaliação podem ser alteradas.",
+            id,
+            entrega.status.descricao()
+        )));
+    }
+    Ok(entrega)
+}
+
+impl Wizard for RecursoConversa {
+    const NOME: &'static str = "recurso";
+
+    async fn avancar(self, message: &Message, ctx: &BotContext) -> Result<Passo<Self>, BotError> {
+        let chat_id = message.chat.id;
+        match self {
+            RecursoConversa::AguardandoArgumento { entrega } => {
+                let Some(argumento) = message.text.as_deref().map(str::trim).filter(|text| !text.is_empty()) else {
+                    send_message(chat_id, "Primeiro, escreva-me seu argumento numa mensagem de texto, guerreiro. As provas novas vêm em seguida.", ctx).await;
+                    return Ok(Passo::Segue(RecursoConversa::AguardandoArgumento { entrega }));
+                };
+                send_message(chat_id, "Argumento anotado. Se tiver provas novas — prints, vídeos ou links —, envie-as agora; quando terminar, use /enviar para levar o recurso aos meus conselheiros.", ctx).await;
+                Ok(Passo::Segue(RecursoConversa::AguardandoProvas {
+                    entrega,
+                    argumento: argumento.to_string(),
+                    provas: Vec::new(),
+                    album: None,
+                }))
+            }
+            RecursoConversa::AguardandoProvas { entrega, argumento, mut provas, mut album } => {
+                if let Some(text) = &message.text {
+                    if text == "/enviar" {
+                        return enviar_recurso(entrega, argumento, provas, message, ctx).await;
+                    }
+                    provas.push(Prova::Texto { texto: text.clone() });
+                    send_message(chat_id, "Registrado. Envie mais provas ou use /enviar.", ctx).await;
+                    return Ok(Passo::Segue(RecursoConversa::AguardandoProvas { entrega, argumento, provas, album }));
+                }
+
+                let (midia, file_id) = match attachment(message) {
+                    Ok(attachment) => attachment,
+                    Err(recusa) => {
+                        send_message(chat_id, recusa, ctx).await;
+                        return Ok(Passo::Segue(RecursoConversa::AguardandoProvas { entrega, argumento, provas, album }));
+                    }
+                };
+                // Um álbum é confirmado, ou recusado, uma vez só
+                let same_album = message.media_group_id.is_some() && message.media_group_id == album;
+                album = message.media_group_id.clone();
+                let arquivos = provas.iter().filter(|prova| prova.caminho().is_some()).count();
+                if arquivos >= ctx.config.limites.arquivos_por_entrega {
+                    if !same_album {
+                        let recusa = format!("Já recebi {} arquivos neste recurso, o máximo que aceito. Use /enviar para levá-lo aos meus conselheiros.", arquivos);
+                        send_message(chat_id, &recusa, ctx).await;
+                    }
+                    return Ok(Passo::Segue(RecursoConversa::AguardandoProvas { entrega, argumento, provas, album }));
+                }
+                let time = ctx.storage.buscar_entrega(entrega)?.map(|e| e.time).unwrap_or_default();
+                if let Some(prova) = baixar_prova(midia, file_id, &time, message, ctx).await {
+                    provas.push(prova);
+                    if let Some(caption) = message.caption.as_ref().filter(|c| !c.trim().is_empty()) {
+                        provas.push(Prova::Texto { texto: caption.clone() });
+                    }
+                    if !same_album {
+                        send_message(chat_id, "Prova recebida. Envie mais ou use /enviar.", ctx).await;
+                    }
+                }
+                Ok(Passo::Segue(RecursoConversa::AguardandoProvas { entrega, argumento, provas, album }))
+            }
+        }
+    }
+
+    fn arquivos(&self) -> Vec<String> {
+        match self {
+            RecursoConversa::AguardandoArgumento { .. } => Vec::new(),
+            RecursoConversa::AguardandoProvas { provas, .. } => provas.iter().filter_map(Prova::caminho).map(str::to_string).collect(),
+        }
+    }
+
+    fn comandos(&self) -> &'static [&'static str] {
+        match self {
+            RecursoConversa::AguardandoArgumento { .. } => &[],
+            RecursoConversa::AguardandoProvas { .. } => &["/enviar"],
+        }
+    }
+
+    fn dica(&self) -> &'static str {
+        match self {
+            RecursoConversa::AguardandoArgumento { .. } => "Escreva-me seu argumento contra a recusa. Se desistiu do recurso, use /cancelar.",
+            RecursoConversa::AguardandoProvas { .. } => "Não conheço essa ordem, guerreiro. Envie suas provas novas, use /enviar para levar o recurso ou /cancelar para desistir.",
+        }
+    }
+}
+
+// A entrega de número `id` e a recusa contra a qual se recorre, se a entrega for do jogador, tiver
+// sido recusada e ainda não tiver recurso
+fn entrega_recorrivel(id: u32, user_id: i64, ctx: &BotContext) -> Result<(Entrega, Avaliacao), BotError> {
+    let entrega = ctx.storage.buscar_entrega(id)?.filter(|entrega| id > 0 && entrega.user_id == Some(user_id));
+    let entrega = entrega.ok_or_else(|| {
+        BotError::Recusa(format!("Não encontro a entrega Nº {} entre as suas, guerreiro. Confira os números em /minhasentregas.", id))
+    })?;
+    let Some(recusa) = entrega.avaliacao.clone().filter(|_| entrega.status == StatusEntrega::Recusada) else {
+        return Err(BotError::Recusa(format!("A entrega Nº {} está {}; só cabe recurso contra uma recusa.", id, entrega.status.descricao())));
+    };
+    if entrega.recurso.is_some() {
+        return Err(BotError::Recusa(format!("Já recebi um recurso contra a recusa da entrega Nº {}; cada recusa admite um só.", id)));
+    }
+    Ok((entrega, recusa))
+}
+
+// Fim do /recorrer: registra o recurso na entrega e o leva ao grupo de administração, no fio da
+// entrega, com os botões de decisão
+async fn enviar_recurso(
+    id: u32,
+    argumento: String,
+    provas: Vec<Prova>,
+    message: &Message,
+    ctx: &BotContext,
+) -> Result<Passo<RecursoConversa>, BotError> {
+    let chat_id = message.chat.id;
+    let user = sender(message)?;
+    let registro = entrega_recorrivel(id, user.id as i64, ctx).and_then(|(_, recusa)| {
+        let recurso = Recurso {
+            argumento: argumento.clone(),
+            textos: provas.iter().filter_map(|prova| match prova {
+                Prova::Texto { texto } | Prova::Resumo { texto, .. } => Some(texto.clone()),
+                Prova::Arquivo { .. } => None,
+            }).collect(),
+            arquivos: provas.iter().filter_map(Prova::caminho).map(str::to_string).collect(),
+            mensagens_admin: Vec::new(),
+            recusa,
+            aberto_em: Utc::now().timestamp(),
+            decisao: None,
+        };
+        // Conferida de novo dentro da trava, para que dois /enviar não abram dois recursos
+        ctx.storage.update_entrega(id, |entrega| {
+            if entrega.recurso.is_some() || entrega.status != StatusEntrega::Recusada {
+                return Err(BotError::Recusa(format!("A entrega Nº {} não admite mais recurso.", id)));
+            }
+            entrega.recurso = Some(recurso);
+            Ok(())
+        })
+    });
+    let entrega = match registro {
+        Ok(Some(entrega)) => entrega,
+        Ok(None) => return Ok(Passo::Fim),
+        Err(BotError::Recusa(recusa)) => {
+            send_message(chat_id, &format!("{} Use /cancelar para encerrar.", recusa), ctx).await;
+            return Ok(Passo::Segue(RecursoConversa::AguardandoProvas { entrega: id, argumento, provas, album: None }));
+        }
+        Err(e) => return Err(e),
+    };
+    let Some(recurso) = &entrega.recurso else {
+        return Ok(Passo::Fim);
+    };
+
+    let missao = entrega.missao.as_ref().map_or("missão não informada".to_string(), |missao| format!("missão do {}", missao));
+    let mut texto = format!(
+        "⚖️ Recurso de {} (@{}) contra a recusa da entrega Nº {}, do time {} ({}):\n\nRecusada por {}: {}\n\nArgumento:\n{}\n",
+        entrega.nome,
+        entrega.user,
+        id,
+        entrega.time.to_uppercase(),
+        missao,
+        recurso.recusa.admin,
+        recurso.recusa.motivo.as_deref().unwrap_or("sem motivo"),
+        recurso.argumento
+    );
+    if !recurso.textos.is_empty() {
+        texto.push_str("\nProvas novas:\n");
+        for prova in &recurso.textos {
+            texto.push_str(&format!("- {}\n", prova));
+        }
+    }
+    let botoes = vec![
+        InlineKeyboardButton::builder().text("⚖️ Manter a recusa").callback_data(format!("recurso:manter:{}", id)).build(),
+        InlineKeyboardButton::builder().text("✅ Aceitar e aprovar").callback_data(format!("recurso:reverter:{}", id)).build(),
+    ];
+    let teclado = InlineKeyboardMarkup::builder().inline_keyboard(vec![botoes]).build();
+    let admin_group_id = ctx.config.grupos.admin;
+    let params = SendMessageParams::builder()
+        .chat_id(admin_group_id)
+        .text(texto.trim_end())
+        .maybe_reply_parameters(reply_to(entrega.mensagens_admin.first().copied()))
+        .reply_markup(ReplyMarkup::InlineKeyboardMarkup(teclado))
+        .build();
+    let mut mensagens = Vec::new();
+    if let Some(enviada) = call_admin(ctx, "sendMessage", ctx.bot.send_message(&params)).await {
+        mensagens.push(enviada.message_id);
+    }
+    if provas.iter().any(|prova| prova.caminho().is_some()) {
+        let legenda = format!("📎 Provas novas do recurso da entrega Nº {}", id);
+        let (ids, _) = send_to_admin(admin_group_id, &legenda, &provas, mensagens.first().copied(), ctx).await;
+        mensagens.extend(ids);
+    }
+
+    let atualizada = ctx.storage.update_entrega(id, |entrega| {
+        if let Some(recurso) = &mut entrega.recurso {
+            recurso.mensagens_admin = mensagens;
+        }
+        Ok(())
+    })?;
+    if let Some(entrega) = atualizada {
+        update_admin_summary(&entrega, ctx).await;
+    }
+    info!(entrega = id, "Recurso aberto");
+    send_message(chat_id, "Seu recurso foi levado aos meus conselheiros. Avisarei assim que decidirem, guerreiro.", ctx).await;
+    Ok(Passo::Fim)
+}
+
+// Resumos só valem como tal quando encaminhados de um bot; digitados, são um texto qualquer
+fn forwarded_from_bot(message: &Message) -> Option<String> {
+    match message.forward_origin.as_deref()? {
+        MessageOrigin::User(origin) if origin.sender_user.is_bot => {
+            Some(origin.sender_user.username.clone().unwrap_or_else(|| origin.sender_user.first_name.clone()))
+        }
+        _ => None,
+    }
+}
+
+// Arquivo aceito como prova: fotos, imagens enviadas como documento, vídeos e vídeos redondos.
+// O erro é a resposta ao usuário
+fn attachment(message: &Message) -> Result<(Midia, &str), &'static str> {
+    if let Some(photo) = message.photo.as_ref().and_then(|sizes| sizes.last()) {
+        return Ok((Midia::Foto, &photo.file_id));
+    }
+    if let Some(document) = &message.document {
+        if document.mime_type.as_deref().is_some_and(|mime| mime.starts_with("image/")) {
+            return Ok((Midia::Documento, &document.file_id));
+        }
+        return Err("Este pergaminho não me serve, guerreiro. Como arquivo, aceito apenas imagens; envie também vídeos, fotos ou textos.");
+    }
+    if let Some(video) = &message.video {
+        return Ok((Midia::Video, &video.file_id));
+    }
+    if let Some(video_note) = &message.video_note {
+        return Ok((Midia::VideoNota, &video_note.file_id));
+    }
+    Err("Não posso aceitar esse tipo de prova. Envie imagens, vídeos ou textos, ou use /entregar para finalizar.")
+}
+
+async fn handle_command_logic(text: &str, message: &Message, ctx: &BotContext) -> Result<(), BotError> {
+    let chat_id = message.chat.id;
+    let user_id = message.from.as_ref().map_or(0, |u| u.id as i64);
+
+    match text {
+        "/start" => send_message(chat_id, "Saudações, nobre guerreiro! Eu, Yuan Shao, líder da aliança contra a tirania, dou-lhe as boas-vindas. O que o traz à minha presença?", ctx).await,
+        // "/inscricao" => {
+        //     if message.chat.type_field != ChatType::Private {
+        //         send_message(chat_id, "Meu nobre, para se juntar à minha causa, peço que me chame em particular. A discrição é uma virtude dos grandes líderes.", ctx).await;
+        //         return;
+        //     }
+        //     let user = message.from.as_ref().unwrap();
+        //     let inscritos = ctx.storage.read_inscritos().unwrap_or_default();
+        //     if inscritos.iter().any(|i| i.user == user.username.as_deref().unwrap_or("")) {
+        //         send_message(chat_id, "Guerreiro, sua lealdade já foi registrada. Você já faz parte de minha nobre aliança!", ctx).await;
+        //         return;
+        //     }
+        //     ctx.user_states.insert(user_id, UserState::Inscricao(Inscricao::AguardandoConfirmacao)).await;
+        //     send_message(chat_id, "Você, nobre guerreiro, deseja jurar lealdade a mim, Yuan Shao, e se inscrever em minha gloriosa campanha? Responda com 'sim' para selar seu destino.", ctx).await;
+        // }
+        "/inscritos" => {
+            if !ctx.config.is_admin_group(chat_id) {
+                return Err(BotError::Permission("Este comando só pode ser utilizado no grupo de administradores."));
+            }
+            let inscritos = ctx.storage.read_inscritos()?;
+            if inscritos.is_empty() {
+                send_message(chat_id, "Minha nobre aliança ainda não possui membros. Seja o primeiro a se juntar à minha causa gloriosa usando /inscricao !", ctx).await;
+            } else {
+                let mut response = String::from("Estes são os nobres guerreiros que juraram lealdade a mim:\n\n");
+                for inscrito in inscritos {
+                    response.push_str(&format!("- Inscrição Nº {}: {} (@{})\n", inscrito.id, inscrito.nome, inscrito.user));
+                }
+                send_message(chat_id, &response, ctx).await;
+            }
+        }
+        "/entregarmissao" => {
+            if message.chat.type_field != ChatType::Private {
+                return Err(BotError::Permission("Meu nobre, para me apresentar seus feitos, peço que o faça em particular. A glória de seus atos não deve ser ofuscada."));
+            }
+            if ctx.user_states.contains(user_id).await {
+                send_message(chat_id, "Já estamos no meio de uma conversa, nobre guerreiro. Conclua-a ou use /cancelar antes de começar outra entrega.", ctx).await;
+                return Ok(());
+            }
+            if let Some(UserState::Entrega(EntregaConversa::AguardandoEntregas(coleta))) = conversa::retomar_rascunho(user_id, ctx).await? {
+                let destino = match coleta.complementa {
+                    Some(id) => format!("para a entrega Nº {}", id),
+                    None => format!("de {}", coleta.time.to_uppercase()),
+                };
+                let text = format!(
+                    "Bem-vindo de volta, guerreiro! Guardei as {} provas {} que você já havia me enviado. Envie as que faltam ou use /entregar para finalizar; /cancelar descarta o rascunho.",
+                    coleta.entregas.len(),
+                    destino
+                );
+                send_message(chat_id, &text, ctx).await;
+                return Ok(());
+            }
+            ctx.user_states.insert(user_id, UserState::Entrega(EntregaConversa::AguardandoTime)).await;
+            send_message(chat_id, "Nobre guerreiro, antes de me apresentar seus feitos, diga-me a qual das grandes casas você jurou lealdade? (Shu, Wei ou Wu)", ctx).await;
+        }
+        "/minhasentregas" => {
+            if message.chat.type_field != ChatType::Private {
+                return Err(BotError::Permission("Meu nobre, seus feitos são assunto nosso; chame-me em particular para consultá-los."));
+            }
+            let entregas = ctx.storage.entregas_do_jogador(user_id)?;
+            send_message(chat_id, &format_minhas_entregas(&entregas), ctx).await;
+        }
+        _ if matches!(command_name(message), Some("/aprovar" | "/recusar")) => {
+            if !ctx.config.is_admin_group(chat_id) {
+                return Err(BotError::Permission("Este comando só pode ser utilizado no grupo de administradores."));
+            }
+            review_delivery(text, message, command_name(message) == Some("/aprovar"), ctx).await?;
+        }
+        _ if matches!(command_name(message), Some("/fila" | "/estatisticas")) => {
+            if !ctx.config.is_admin_group(chat_id) {
+                return Err(BotError::Permission("Este comando só pode ser utilizado no grupo de administradores."));
+            }
+            let entregas = ctx.storage.todas_entregas()?;
+            let text = if command_name(message) == Some("/fila") {
+                revisao::format_fila(&entregas, ctx.config.grupos.admin, Utc::now().timestamp())
+            } else {
+                revisao::format_estatisticas(&entregas)
+            };
+            send_message(chat_id, &text, ctx).await;
+        }
+        _ if matches!(command_name(message), Some("/pegar" | "/soltar")) => {
+            if !ctx.config.is_admin_group(chat_id) {
+                return Err(BotError::Permission("Este comando só pode ser utilizado no grupo de administradores."));
+            }
+            claim_delivery(text, message, command_name(message) == Some("/pegar"), ctx).await?;
+        }
+        _ if command_name(message) == Some("/retirarentrega") => {
+            if message.chat.type_field != ChatType::Private {
+                return Err(BotError::Permission("Meu nobre, seus feitos são assunto nosso; chame-me em particular para alterá-los."));
+            }
+            let id = numero_entrega(text, "/retirarentrega")?;
+            entrega_pendente(id, user_id, ctx)?;
+            // Conferida de novo dentro da trava, para não retirar uma entrega avaliada nesse meio-tempo
+            let retirada = ctx.storage.update_entrega(id, |entrega| {
+                if entrega.status != StatusEntrega::Pendente {
+                    return Err(BotError::Recusa(format!("A entrega Nº {} já está {}; só as que aguardam avaliação podem ser retiradas.", id, entrega.status.descricao())));
+                }
+                entrega.status = StatusEntrega::Retirada;
+                Ok(())
+            })?;
+            if let Some(entrega) = retirada {
+                info!(entrega = id, "Entrega retirada pelo jogador");
+                update_admin_summary(&entrega, ctx).await;
+            }
+            let text = format!(
+                "Como desejar: a entrega Nº {} foi retirada e não será avaliada. As partidas dela ficam livres para uma nova entrega.",
+                id
+            );
+            send_message(chat_id, &text, ctx).await;
+        }
+        _ if command_name(message) == Some("/recorrer") => {
+            if message.chat.type_field != ChatType::Private {
+                return Err(BotError::Permission("Meu nobre, seus feitos são assunto nosso; chame-me em particular para recorrer."));
+            }
+            if ctx.user_states.contains(user_id).await {
+                send_message(chat_id, "Já estamos no meio de uma conversa, nobre guerreiro. Conclua-a ou use /cancelar antes de recorrer.", ctx).await;
+                return Ok(());
+            }
+            let id = numero_entrega(text, "/recorrer")?;
+            let (_, recusa) = entrega_recorrivel(id, user_id, ctx)?;
+            let text = format!(
+                "Ouvirei seu recurso contra a recusa da entrega Nº {}. O motivo dado por meus conselheiros foi: {}\n\nEscreva-me numa mensagem por que a decisão deve ser revista; depois poderá enviar provas novas. Use /cancelar se mudar de ideia.",
+                id,
+                recusa.motivo.as_deref().unwrap_or("nenhum")
+            );
+            ctx.user_states.insert(user_id, UserState::Recurso(RecursoConversa::AguardandoArgumento { entrega: id })).await;
+            send_message(chat_id, &text, ctx).await;
+        }
+        _ if command_name(message) == Some("/adicionarprovas") => {
+            if message.chat.type_field != ChatType::Private {
+                return Err(BotError::Permission("Meu nobre, seus feitos são assunto nosso; chame-me em particular para alterá-los."));
+            }
+            if ctx.user_states.contains(user_id).await {
+                send_message(chat_id, "Já estamos no meio de uma conversa, nobre guerreiro. Conclua-a ou use /cancelar antes de alterar uma entrega.", ctx).await;
+                return Ok(());
+            }
+            let id = numero_entrega(text, "/adicionarprovas")?;
+            let entrega = entrega_pendente(id, user_id, ctx)?;
+            let missao = entrega.missao.as_ref().map_or(String::new(), |missao| format!(", do {}", missao));
+            let text = format!(
+                "Muito bem. Envie as novas provas para a entrega Nº {}{} e use /entregar quando terminar; /cancelar desiste sem alterá-la.",
+                id, missao
+            );
+            ctx.user_states.insert(user_id, UserState::Entrega(EntregaConversa::AguardandoEntregas(Coleta {
+                time: entrega.time,
+                missao: entrega.missao,
+                complementa: Some(id),
+                entregas: Vec::new(),
+                album: None,
+                album_recusado: None,
+                // O link, se houver, já veio na entrega original
+                sem_link_avisado: true,
+            }))).await;
+            send_message(chat_id, &text, ctx).await;
+        }
+        "/missoes" => {
+            let missoes_data = ctx.storage.read_missoes()?;
+            if missoes_data.is_empty() {
+                send_message(chat_id, "Não há decretos no momento. Aguardem minhas ordens, a glória nos espera!", ctx).await;
+            } else {
+                let full_text = missoes_data.first().map(|m| m.texto.as_str()).unwrap_or("");
+                let lines: Vec<&str> = full_text.lines().collect();
+
+                let mut part1 = String::from("Escutem todos o meu decreto! (Parte 1/2)\n\n");
+                let mut part2 = String::from("Escutem todos o meu decreto! (Parte 2/2)\n\n");
+
+                let mut current_part = 1;
+                for line in lines {
+                    if line.contains("Naipe 11") {
+                        current_part = 2;
+                    }
+                    if current_part == 1 {
+                        part1.push_str(line);
+                        part1.push('\n');
+                    } else {
+                        part2.push_str(line);
+                        part2.push('\n');
+                    }
+                }
+                
+                send_message(chat_id, &part1, ctx).await;
+                tokio::time::sleep(Duration::from_secs(1)).await; // Small delay to avoid rate limits
+                send_message(chat_id, &part2, ctx).await;
+            }
+        }
+        "/verificaremojis" => {
+            if !ctx.config.is_admin_group(chat_id) {
+                return Err(BotError::Permission("Este comando só pode ser utilizado no grupo de administradores."));
+            }
+            let report = emoji_report(ctx).await?;
+            send_message(chat_id, &report, ctx).await;
+        }
+        _ if command_name(message) == Some("/mudartime") => {
+            if !ctx.config.is_admin_group(chat_id) {
+                return Err(BotError::Permission("Este comando só pode ser utilizado no grupo de administradores."));
+            }
+            let args: Vec<String> = text.split_whitespace().skip(1).map(str::to_lowercase).collect();
+            let [user, time] = args.as_slice() else {
+                return Err(BotError::Recusa("Diga o jogador e o novo time, como em /mudartime @usuario wei.".to_string()));
+            };
+            if !TIMES.contains(&time.as_str()) {
+                return Err(BotError::Recusa(format!("Não conheço o time {}; escolha entre shu, wei ou wu.", time)));
+            }
+            let Some((inscrito, anterior)) = ctx.storage.corrigir_time(user, time)? else {
+                return Err(BotError::Recusa(format!("Não encontro {} entre os inscritos.", user)));
+            };
+            let anterior = anterior.map_or("nenhum".to_string(), |anterior| anterior.to_uppercase());
+            info!(user = %inscrito.user, time = %time, "Time do inscrito corrigido");
+            let text = format!(
+                "Time de {} (@{}) corrigido: {} → {}. As entregas já feitas continuam no time em que foram registradas.",
+                inscrito.nome,
+                inscrito.user,
+                anterior,
+                time.to_uppercase()
+            );
+            send_message(chat_id, &text, ctx).await;
+        }
+        "/shu" | "/wei" | "/wu" => {
+            let team_name = text.trim_start_matches('/').to_lowercase();
+            let is_admin_group = ctx.config.is_admin_group(chat_id);
+            let is_correct_team_group = ctx.config.team_group_id(&team_name) == Some(chat_id);
+
+            if !(is_admin_group || is_correct_team_group) {
+                return Err(BotError::Permission("Este comando só pode ser utilizado no grupo de administradores ou no grupo do seu time."));
+            }
+            send_team_db(chat_id, &team_name, ctx).await?;
+        }
+        "/calendario" => {
+            let calendar_message = "🗓 CALENDARIO DO EVENTO 🗓
+
+10/08 - Mensagem secreta de aviso 
+
+14/08 - Anuncio do Evento e abertura das inscrições 
+
+20/08 - Fim das inscrições 
+
+22/08 - Sorteio dos times
+
+23/08 - Rebelião dos Turbantes Amarelos 184 - RUNNING GAME 
+
+26/08 - Campanha contra Dong Zhuo 190 - IMPOSTOR
+
+27/08 - 🧧 A Lenda de Lu Bu 🧧 (Aberto)
+
+28/08 - Batalha de Guandu 200 -  PERGUNTAS & RESPOSTAS
+
+29/08 - Batalha de Xinye 207 -  JOGO DA MEMÓRIA
+
+30/08 - Batalha de Chibi 208 - WAR PPT1
+
+31/08 - Batalha de Nanjun 209 - TRY LUCK
+
+01/09 -🌙  Ascenção à Lua - Contos de Diao Chan 🌙 (Aberto)
+
+02/09 - Batalha da Provincia de Jing 214 - BATALHA NAVAL
+
+04/09 - Batalha de Hefei 215 - SOBREVIVENCIA E ESCOLHAS TEMATICO
+
+06/09 - Batalha de Fan Castle 219  - WAR PPT 2";
+            send_message(chat_id, calendar_message, ctx).await;
+        }
+        "/regras" => {
+            let regras_message = "🚫 Regras do Evento 🚫
+
+● A partir do dia 23/08, à 00h, os times estarão liberados para completar as missões;
+
+● Para completar a missão o jogador deve estar inscrito no evento e jogando com o emoji do time;
+
+● A entrega das missões devem ser feitas no @romancedos3reinos_bot. Envie o print da missão realizada + o link do final da partida;
+
+● Cada time iniciará com 10000 Soldados;
+
+● Não é permitido a entrada de inscritos pós período de inscrição. Apenas em casos de desistência; 
+
+● Cair AFK resultará numa penalidade de -1 ponto para a equipe;
+
+● Missões de AFK não contam;
+
+● É estritamente proibido qualquer tipo de antijogo/ferimento de regras do grupo durante as partidas. As partidas devem ser jogadas normalmente para completar as missões. Caso houver qualquer indício de quebra de regras, o time perderá 20pnts por quebra de regras.";
+            send_message(chat_id, regras_message, ctx).await;
+        }
+        "/premios" => {
+            let premios_message = "🏆 PREMIAÇÃO
+
+🥇 Lugar: 
+
+— 4 Produtos da DH Store [com exceção da corrida maluca e removedor].
+— 5 Removedores de advertência  para cada participante.
+— 300 Lobunos para cada participante.
+
+🥈 Lugar: 
+
+— 3 Produto da DH Store [com exceção dos fks pass, corrida maluca e removedor].
+— 3 Removedores de advertência para cada participante.
+— 200 Lobunos para cada participante.
+
+🥉 Lugar:
+
+— 2 Produto da DH Store [com exceção dos fks pass, corrida maluca e removedor].
+— 2 Removedor de advertência para cada participante.
+— 100 Lobunos para cada participante.";
+            send_message(chat_id, premios_message, ctx).await;
+        }
+        _ => {
+            // Comandos de Admin
+            if ctx.config.is_admin_group(chat_id) && (text.starts_with("/add") || text.starts_with("/remove")) {
+                handle_admin_commands(text, message, ctx).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// /aprovar e /recusar: a entrega vem do número no comando ou da mensagem dela respondida pelo admin,
+// e o resto do texto é o motivo, repassado ao jogador. Aprovar credita a missão ao time
+async fn review_delivery(text: &str, message: &Message, aprovada: bool, ctx: &BotContext) -> Result<(), BotError> {
+    let comando = if aprovada { "/aprovar" } else { "/recusar" };
+    let (id, motivo) = entrega_do_comando(text, message, comando, ctx)?;
+    if !aprovada && motivo.is_empty() {
+        return Err(BotError::Recusa(format!("Diga o motivo da recusa, como em /recusar {} sem o link da partida; ele será enviado ao jogador.", id)));
+    }
+    avaliar_entrega(id, motivo, aprovada, None, message, ctx).await
+}
+
+// Aprova ou recusa a entrega, avisa o jogador e, na aprovação, o grupo do time. Um /add com o número da
+// entrega traz o time e a missão creditados, que passam a ser os da entrega
+async fn avaliar_entrega(
+    id: u32,
+    motivo: &str,
+    aprovada: bool,
+    credito: Option<(&str, MissaoCumprida)>,
+    message: &Message,
+    ctx: &BotContext,
+) -> Result<(), BotError> {
+    let chat_id = message.chat.id;
+    let admin = sender(message)?;
+    let avaliacao = Avaliacao {
+        admin: display_name(admin),
+        admin_id: admin.id as i64,
+        motivo: (!motivo.is_empty()).then(|| motivo.to_string()),
+        em: Utc::now().timestamp(),
+    };
+    // A situação muda dentro da trava: duas avaliações simultâneas não creditam a missão duas vezes. Na
+    // aprovação, o crédito ao time é gravado junto, e uma falha nele deixa a entrega pendente
+    let avaliar = |entrega: &mut Entrega| {
+        if entrega.status != StatusEntrega::Pendente {
+            return Err(BotError::Recusa(format!("A entrega Nº {} já está {}; nada foi alterado.", id, entrega.status.descricao())));
+        }
+        if let Some((time, missao)) = &credito {
+            if entrega.time != *time {
+                return Err(BotError::Recusa(format!("A entrega Nº {} é do time {}; nada foi creditado.", id, entrega.time.to_uppercase())));
+            }
+            match &entrega.missao {
+                Some(informada) if informada != missao => {
+                    return Err(BotError::Recusa(format!(
+                        "A entrega Nº {} informa a missão do {}; confira o comando ou aprove-a com /aprovar {}.",
+                        id, informada, id
+                    )));
+                }
+                _ => entrega.missao = Some(missao.clone()),
+            }
+        }
+        if aprovada && entrega.missao.is_none() {
+            return Err(BotError::Recusa(format!(
+                "A entrega Nº {} não informa a missão; credite-a com /add {} <naipe> <missão> {}.",
+                id, entrega.time, id
+            )));
+        }
+        if let Some(reserva) = entrega.responsavel.as_ref().filter(|reserva| reserva.admin_id != avaliacao.admin_id) {
+            return Err(BotError::Recusa(format!(
+                "A entrega Nº {} está com {}; use /soltar {} para liberá-la antes de avaliar.",
+                id, reserva.admin, id
+            )));
+        }
+        entrega.status = if aprovada { StatusEntrega::Aprovada } else { StatusEntrega::Recusada };
+        entrega.avaliacao = Some(avaliacao);
+        entrega.responsavel = None;
+        Ok(())
+    };
+    let entrega = if aprovada {
+        ctx.storage.aprovar_entrega(id, avaliar)?
+    } else {
+        ctx.storage.update_entrega(id, avaliar)?
+    };
+    let entrega = entrega.ok_or_else(|| BotError::Recusa(format!("Não encontro a entrega Nº {}.", id)))?;
+    let missao = entrega.missao.as_ref().map_or("missão não informada".to_string(), |missao| format!("missão do {}", missao));
+
+    if aprovada {
+        anunciar_entrega(&entrega, ctx).await;
+        info!(entrega = id, "Entrega aprovada");
+    } else {
+        info!(entrega = id, "Entrega recusada");
+    }
+    update_admin_summary(&entrega, ctx).await;
+
+    let palavras = match (aprovada, motivo.is_empty()) {
+        (true, true) => String::new(),
+        (true, false) => format!("\n\nPalavras do conselheiro: {}", motivo),
+        (false, _) => format!("\n\nMotivo: {}", motivo),
+    };
+    let aviso = if aprovada {
+        format!(
+            "Glória a você, guerreiro! Sua entrega Nº {}, da {}, foi aprovada por meus conselheiros e já conta para {}.{}",
+            id,
+            missao,
+            entrega.time.to_uppercase(),
+            palavras
+        )
+    } else {
+        format!(
+            "Guerreiro, sua entrega Nº {}, da {}, não foi aceita por meus conselheiros.{}\n\nConsulte as /regras e tente de novo em outra partida. Se julgar a decisão injusta, use /recorrer {}.",
+            id, missao, palavras, id
+        )
+    };
+    let avisado = match entrega.user_id {
+        Some(user_id) => {
+            send_message(user_id, &aviso, ctx).await;
+            format!("{} recebeu o aviso.", entrega.nome)
+        }
+        None => format!("{} não pôde receber o aviso: a entrega é de antes de o bot guardar o ID do jogador.", entrega.nome),
+    };
+    let confirmacao = if aprovada {
+        format!("Entrega Nº {} aprovada e {} creditada ao time {}. {}", id, missao, entrega.time.to_uppercase(), avisado)
+    } else {
+        format!("Entrega Nº {} recusada. {}", id, avisado)
+    };
+    // Responde às provas, para a decisão ficar no fio da entrega
+    send_admin_text(chat_id, &confirmacao, entrega.mensagens_admin.first().copied(), ctx).await;
+    Ok(())
+}
+
+// A entrega de um comando de admin, pela mensagem dela respondida ou pelo primeiro argumento, e o resto
+// do texto depois do número
+fn entrega_do_comando<'a>(text: &'a str, message: &Message, comando: &str, ctx: &BotContext) -> Result<(u32, &'a str), BotError> {
+    let argumentos = text.split_once(char::is_whitespace).map_or("", |(_, resto)| resto.trim());
+    let respondida = match &message.reply_to_message {
+        Some(resposta) => ctx.storage.entrega_da_mensagem_admin(resposta.message_id)?,
+        None => None,
+    };
+    if let Some(entrega) = respondida {
+        return Ok((entrega.id, argumentos));
+    }
+    let (numero, resto) = argumentos.split_once(char::is_whitespace).unwrap_or((argumentos, ""));
+    let id = numero.trim_start_matches('#').parse::<u32>().map_err(|_| {
+        BotError::Recusa(format!("Diga o número da entrega, como em {} 12, ou responda a uma das mensagens dela.", comando))
+    })?;
+    Ok((id, resto.trim()))
+}
+
+// /pegar avisa aos outros admins que a entrega está em avaliação; /soltar a devolve à fila, seja quem for
+// que a pegou. Só quem pegou uma entrega pode aprová-la ou recusá-la enquanto ela estiver reservada
+async fn claim_delivery(text: &str, message: &Message, pegar: bool, ctx: &BotContext) -> Result<(), BotError> {
+    let comando = if pegar { "/pegar" } else { "/soltar" };
+    let (id, _) = entrega_do_comando(text, message, comando, ctx)?;
+    let admin = sender(message)?;
+    let reserva = Reserva { admin: display_name(admin), admin_id: admin.id as i64, em: Utc::now().timestamp() };
+    let mut anterior = None;
+    let entrega = ctx.storage.update_entrega(id, |entrega| {
+        if entrega.status != StatusEntrega::Pendente {
+            let acao = if pegar { "reservar" } else { "liberar" };
+            return Err(BotError::Recusa(format!("A entrega Nº {} já está {}; não há o que {}.", id, entrega.status.descricao(), acao)));
+        }
+        match (&entrega.responsavel, pegar) {
+            (Some(atual), true) if atual.admin_id == reserva.admin_id => {
+                return Err(BotError::Recusa(format!("A entrega Nº {} já está com você.", id)));
+            }
+            (Some(atual), true) => {
+                return Err(BotError::Recusa(format!("A entrega Nº {} já está com {}.", id, atual.admin)));
+            }
+            (None, false) => return Err(BotError::Recusa(format!("A entrega Nº {} não está reservada.", id))),
+            _ => {}
+        }
+        anterior = entrega.responsavel.take();
+        if pegar {
+            entrega.responsavel = Some(reserva.clone());
+        }
+        Ok(())
+    })?;
+    let entrega = entrega.ok_or_else(|| BotError::Recusa(format!("Não encontro a entrega Nº {}.", id)))?;
+    update_admin_summary(&entrega, ctx).await;
+
+    let confirmacao = match anterior {
+        None => {
+            info!(entrega = id, "Entrega reservada");
+            format!("Entrega Nº {} reservada para {}. Avalie-a com /aprovar ou /recusar; /soltar {} a devolve à fila.", id, reserva.admin, id)
+        }
+        Some(anterior) => {
+            info!(entrega = id, "Entrega devolvida à fila");
+            format!("Entrega Nº {} devolvida à fila; estava com {}.", id, anterior.admin)
+        }
+    };
+    send_message(message.chat.id, &confirmacao, ctx).await;
+    Ok(())
+}
+
+// Anuncia no grupo do time a missão de uma entrega aprovada, com o nome do jogador
+async fn anunciar_entrega(entrega: &Entrega, ctx: &BotContext) {
+    let Some(missao) = &entrega.missao else {
+        return;
+    };
+    if let Some(team_group_id) = ctx.config.team_group_id(&entrega.time) {
+        let text = format!(
+            "Atenção, guerreiros de {}! {} cumpriu a missão do {}, agora registrada em nome da casa. Que a glória os acompanhe!",
+            entrega.time.to_uppercase(),
+            entrega.nome,
+            missao
+        );
+        send_message(team_group_id, &text, ctx).await;
+    }
+}
+
+// Span dos cliques em botões, com os mesmos campos de update_span
+pub(crate) fn callback_span(update_id: u32, query: &CallbackQuery) -> tracing::Span {
+    info_span!(
+        "update",
+        update_id,
+        chat_id = callback_chat(query),
+        user_id = query.from.id,
+        command = query.data.as_deref(),
+    )
+}
+
+fn callback_chat(query: &CallbackQuery) -> i64 {
+    match &query.message {
+        Some(MaybeInaccessibleMessage::Message(message)) => message.chat.id,
+        Some(MaybeInaccessibleMessage::InaccessibleMessage(message)) => message.chat.id,
+        None => 0,
+    }
+}
+
+// Cliques nos botões das mensagens do bot; hoje, só os de decisão de recurso. A resposta aparece
+// como aviso para quem clicou
+pub async fn process_callback(query: CallbackQuery, ctx: &BotContext) {
+    ctx.metrics.update_processed();
+    let resposta = match decide_appeal(&query, ctx).await {
+        Ok(resposta) => resposta,
+        Err(error) => {
+            match error {
+                BotError::Parse(_) | BotError::Permission(_) | BotError::Recusa(_) => warn!(kind = error.kind(), error = %error, "Botão recusado"),
+                _ => error!(kind = error.kind(), error = %error, "Falha ao processar botão"),
+            }
+            error.user_message().to_string()
+        }
+    };
+    let params = AnswerCallbackQueryParams::builder().callback_query_id(query.id.clone()).text(resposta).build();
+    if let Err(e) = ctx.bot.answer_callback_query(&params).await {
+        ctx.metrics.telegram_error("answerCallbackQuery");
+        warn!(error = %e, "Falha ao responder ao botão");
+    }
+}
+
+// Botões "recurso:manter:<nº>" e "recurso:reverter:<nº>". Só a primeira decisão vale; reverter aprova a
+// entrega e credita a missão
+async fn decide_appeal(query: &CallbackQuery, ctx: &BotContext) -> Result<String, BotError> {
+    if !ctx.config.is_admin_group(callback_chat(query)) {
+        return Err(BotError::Permission("Só os administradores decidem recursos."));
+    }
+    let dados = query.data.as_deref().unwrap_or("");
+    let (revertida, id) = match dados.split(':').collect::<Vec<_>>()[..] {
+        ["recurso", "manter", id] => (false, id),
+        ["recurso", "reverter", id] => (true, id),
+        _ => return Err(BotError::Parse(format!("botão desconhecido: {}", dados))),
+    };
+    let id = id.parse::<u32>().map_err(|e| BotError::Parse(format!("número da entrega no botão: {}", e)))?;
+
+    let admin = display_name(&query.from);
+    let admin_id = query.from.id as i64;
+    let em = Utc::now().timestamp();
+    // Reverter aprova a entrega: a decisão e o crédito ao time são gravados juntos, como em /aprovar
+    let decidir = |entrega: &mut Entrega| {
+        let Some(recurso) = &mut entrega.recurso else {
+            return Err(BotError::Recusa(format!("A entrega Nº {} não tem recurso.", id)));
+        };
+        if let Some(decisao) = &recurso.decisao {
+            return Err(BotError::Recusa(format!("Este recurso já foi decidido por {}.", decisao.admin)));
+        }
+        if revertida && entrega.missao.is_none() {
+            return Err(BotError::Recusa(format!("A entrega Nº {} não informa a missão; mantenha a recusa e credite-a com /add.", id)));
+        }
+        recurso.decisao = Some(DecisaoRecurso { revertida, admin: admin.clone(), admin_id, em });
+        if revertida {
+            entrega.status = StatusEntrega::Aprovada;
+            entrega.avaliacao = Some(Avaliacao {
+                admin: admin.clone(),
+                admin_id,
+                motivo: Some("recusa revertida em recurso".to_string()),
+                em,
+            });
+        }
+        Ok(())
+    };
+    let entrega = if revertida {
+        ctx.storage.aprovar_entrega(id, decidir)?
+    } else {
+        ctx.storage.update_entrega(id, decidir)?
+    };
+    let entrega = entrega.ok_or_else(|| BotError::Recusa(format!("Não encontro a entrega Nº {}.", id)))?;
+    let missao = entrega.missao.as_ref().map_or("missão não informada".to_string(), |missao| format!("missão do {}", missao));
+
+    if revertida {
+        anunciar_entrega(&entrega, ctx).await;
+    }
+    info!(entrega = id, revertida, "Recurso decidido");
+    update_admin_summary(&entrega, ctx).await;
+
+    // A mensagem do recurso perde os botões e ganha a decisão
+    if let Some(MaybeInaccessibleMessage::Message(mensagem)) = &query.message {
+        let decisao = if revertida {
+            format!("✅ Recurso aceito por {}: entrega aprovada.", admin)
+        } else {
+            format!("⚖️ Recurso negado por {}: a recusa foi mantida.", admin)
+        };
+        let params = EditMessageTextParams::builder()
+            .chat_id(mensagem.chat.id)
+            .message_id(mensagem.message_id)
+            .text(format!("{}\n\n{}", mensagem.text.as_deref().unwrap_or(""), decisao))
+            .build();
+        call_admin(ctx, "editMessageText", ctx.bot.edit_message_text(&params)).await;
+    }
+
+    if let Some(user_id) = entrega.user_id {
+        let aviso = if revertida {
+            format!(
+                "Boas notícias, guerreiro! Meus conselheiros aceitaram seu recurso: a entrega Nº {}, da {}, foi aprovada e já conta para {}.",
+                id,
+                missao,
+                entrega.time.to_uppercase()
+            )
+        } else {
+            format!(
+                "Guerreiro, meus conselheiros analisaram seu recurso e mantiveram a recusa da entrega Nº {}, da {}. A decisão é final.",
+                id, missao
+            )
+        };
+        send_message(user_id, &aviso, ctx).await;
+    }
+    Ok(if revertida {
+        format!("Entrega Nº {} aprovada em recurso.", id)
+    } else {
+        format!("Recusa da entrega Nº {} mantida.", id)
+    })
+}
+
+// Número da entrega no argumento de um comando, como em "/retirarentrega 12" ou "/retirarentrega Nº 12"
+fn numero_entrega(text: &str, comando: &str) -> Result<u32, BotError> {
+    let numero_re = Regex::new(r"^\S+\s+(?:n[º°o]\.?\s*)?#?(\d+)\s*$").unwrap();
+    numero_re
+        .captures(&text.to_lowercase())
+        .and_then(|caps| caps[1].parse().ok())
+        .ok_or_else(|| {
+            BotError::Recusa(format!("Diga-me o número da entrega, guerreiro, como em {} 12. Os números estão em /minhasentregas.", comando))
+        })
+}
+
+// Lista de entregas de um jogador, com a situação de cada uma
+fn format_minhas_entregas(entregas: &[Entrega]) -> String {
+    if entregas.is_empty() {
+        return "Você ainda não me apresentou nenhum feito, guerreiro. Use /entregarmissao quando tiver cumprido uma missão.".to_string();
+    }
+    let mut text = String::from("📜 Seus feitos registrados, nobre guerreiro:\n");
+    for entrega in entregas {
+        let numero = if entrega.id > 0 { format!("Nº {}", entrega.id) } else { "Sem número".to_string() };
+        let missao = entrega.missao.as_ref().map_or("missão não informada".to_string(), |missao| missao.to_string());
+        let recurso = match entrega.recurso.as_ref().map(|recurso| &recurso.decisao) {
+            None => "",
+            Some(None) => " · ⚖️ recurso em análise",
+            Some(Some(decisao)) if decisao.revertida => " · ⚖️ recurso aceito",
+            Some(Some(_)) => " · ⚖️ recurso negado",
+        };
+        text.push_str(&format!(
+            "\n{} — {}, time {}\n{}{} · {} arquivo(s), {} texto(s)\n",
+            numero,
+            missao,
+            entrega.time.to_uppercase(),
+            entrega.status.descricao(),
+            recurso,
+            entrega.arquivos(),
+            entrega.textos.len() + entrega.resumos.len()
+        ));
+    }
+    if entregas.iter().any(|entrega| entrega.status == StatusEntrega::Pendente && entrega.id > 0) {
+        text.push_str("\nEnquanto uma entrega aguarda avaliação, use /adicionarprovas <nº> para juntar novas provas ou /retirarentrega <nº> para retirá-la.");
+    }
+    if entregas.iter().any(|entrega| entrega.status == StatusEntrega::Recusada && entrega.recurso.is_none() && entrega.id > 0) {
+        text.push_str("\nDiscorda de uma recusa? Use /recorrer <nº> para pedir que meus conselheiros a revejam.");
+    }
+    text
+}
+
+pub(crate) async fn send_message(chat_id: i64, text: &str, ctx: &BotContext) {
+    ctx.outbox.push(ctx, chat_id, text).await;
+}
+
+// Limites do Telegram para a legenda e para a quantidade de itens de um álbum. A legenda é medida em
+// unidades UTF-16, como o Telegram conta: cada emoji dos resumos vale duas ou mais
+const CAPTION_LIMIT: usize = 1024;
+const ALBUM_LIMIT: usize = 10;
+
+// Resumo de uma entrega para os administradores: missão, textos, pré-avaliação dos resumos de partida
+// e provas repetidas, sob o título dado
+fn admin_summary(admin_group_id: i64, titulo: &str, entrega: &Entrega, repetidas: &[Repetida], ctx: &BotContext) -> String {
+    let mut admin_message = format!("{}\n\n", titulo);
+
+    match &entrega.missao {
+        Some(missao) => admin_message.push_str(&format!("Missão: {}\n", missao)),
+        None => admin_message.push_str("Missão: não informada\n"),
+    }
+    if entrega.partidas.is_empty() {
+        admin_message.push_str("⚠️ Sem link da partida\n");
+    }
+    admin_message.push('\n');
+
+    if !entrega.textos.is_empty() {
+        admin_message.push_str("Textos:\n");
+        for texto in &entrega.textos {
+            admin_message.push_str(&format!("- {}\n", texto));
+        }
+    }
+
+    for resumo in entrega.resumos.iter().filter_map(|texto| parse_resumo(texto)) {
+        let descricao = entrega.missao.as_ref().and_then(|missao| {
+            let missoes = ctx.storage.read_missoes().ok()?;
+            parse_missao_descricao(&missoes.first()?.texto, missao)
+        });
+        let veredito = resumo::avaliar(&resumo, &entrega.nome, descricao.as_deref(), ctx.config.team_emoji(&entrega.time));
+        let conclusao = if veredito.provavelmente_valida() { "provavelmente válida" } else { "provavelmente inválida" };
+        admin_message.push_str(&format!("\n🔎 Resumo da partida: {}\n", conclusao));
+        for item in &veredito.confere {
+            admin_message.push_str(&format!("✓ {}\n", item));
+        }
+        for item in &veredito.problemas {
+            admin_message.push_str(&format!("✗ {}\n", item));
+        }
+    }
+
+    if !repetidas.is_empty() {
+        admin_message.push_str("\n⚠️ Provas já apresentadas antes:\n");
+        for repetida in repetidas {
+            let anterior = match &repetida.anterior {
+                Anterior::Entrega(anterior) => anterior,
+                Anterior::MesmaEntrega(prova) => {
+                    admin_message.push_str(&format!(
+                        "- arquivo {} {} arquivo {} destas mesmas provas\n",
+                        repetida.prova,
+                        if repetida.exata { "é o mesmo que o" } else { "é quase igual ao" },
+                        prova
+                    ));
+                    continue;
+                }
+            };
+            let link = anterior.mensagens_admin.first()
+                .and_then(|id| message_link(admin_group_id, *id))
+                .map(|link| format!(": {}", link))
+                .unwrap_or_default();
+            let numero = if anterior.id > 0 { format!(" Nº {}", anterior.id) } else { String::new() };
+            admin_message.push_str(&format!(
+                "- arquivo {} {} da entrega{} de {} (@{}) para o time {}{}\n",
+                repetida.prova,
+                if repetida.exata { "é o mesmo" } else { "é quase igual a um" },
+                numero,
+                anterior.nome,
+                anterior.user,
+                anterior.time.to_uppercase(),
+                link
+            ));
+        }
+    }
+    admin_message.trim_end().to_string()
+}
+
+// Envia um resumo e as provas ao grupo de administração como álbum, reaproveitando o file_id de cada
+// prova em vez de subir o arquivo de novo. Devolve os IDs das mensagens enviadas, a primeira delas sendo
+// a do resumo, e se o resumo foi como legenda do primeiro álbum
+async fn send_to_admin(
+    admin_group_id: i64,
+    admin_message: &str,
+    entregas: &[Prova],
+    responde_a: Option<i32>,
+    ctx: &BotContext,
+) -> (Vec<i32>, bool) {
+
+    // O Telegram não mistura documentos com fotos e vídeos num mesmo álbum, e notas de vídeo não entram em álbuns
+    let mut visuais = Vec::new();
+    let mut documentos = Vec::new();
+    let mut notas = Vec::new();
+    for prova in entregas {
+        match prova {
+            Prova::Arquivo { midia: Midia::Documento, file_id, .. } => documentos.push((Midia::Documento, file_id.as_str())),
+            Prova::Arquivo { midia: Midia::VideoNota, file_id, .. } => notas.push(file_id.as_str()),
+            Prova::Arquivo { midia, file_id, .. } => visuais.push((*midia, file_id.as_str())),
+            Prova::Texto { .. } | Prova::Resumo { .. } => {}
+        }
+    }
+    let albuns: Vec<&[(Midia, &str)]> = visuais.chunks(ALBUM_LIMIT).chain(documentos.chunks(ALBUM_LIMIT)).collect();
+
+    // Um resumo curto vira a legenda do primeiro álbum; sem álbum, ou longo demais, segue como mensagem própria
+    let mut ids = Vec::new();
+    let mut legenda = !albuns.is_empty() && cabe_na_legenda(admin_message);
+    if !legenda {
+        ids.extend(send_admin_text(admin_group_id, admin_message, responde_a, ctx).await);
+    }
+
+    for (i, album) in albuns.into_iter().enumerate() {
+        let caption = (i == 0 && legenda).then_some(admin_message);
+        if let Some(messages) = send_admin_album(admin_group_id, album, caption, ids.first().copied().or(responde_a), ctx).await {
+            ids.extend(messages);
+            continue;
+        }
+        // Sem o álbum com a legenda, os admins não receberiam nada: o resumo vai como texto e o álbum é
+        // tentado mais uma vez, sem legenda, respondendo a ele
+        if caption.is_some() {
+            legenda = false;
+            ids.extend(send_admin_text(admin_group_id, admin_message, responde_a, ctx).await);
+            let retry = send_admin_album(admin_group_id, album, None, ids.first().copied().or(responde_a), ctx).await;
+            ids.extend(retry.into_iter().flatten());
+        }
+    }
+
+    for file_id in notas {
+        let params = SendVideoNoteParams::builder()
+            .chat_id(admin_group_id)
+            .video_note(FileUpload::String(file_id.to_string()))
+            .maybe_reply_parameters(reply_to(ids.first().copied().or(responde_a)))
+            .build();
+        if let Some(message) = call_admin(ctx, "sendVideoNote", ctx.bot.send_video_note(&params)).await {
+            ids.push(message.message_id);
+        }
+    }
+    (ids, legenda)
+}
+
+fn cabe_na_legenda(texto: &str) -> bool {
+    texto.encode_utf16().count() <= CAPTION_LIMIT
+}
+
+// Corta o texto para caber numa legenda, terminando em reticências
+fn cortar_legenda(texto: &str) -> String {
+    if cabe_na_legenda(texto) {
+        return texto.to_string();
+    }
+    let mut cortado = String::new();
+    let mut unidades = 0;
+    for c in texto.chars() {
+        unidades += c.len_utf16();
+        if unidades > CAPTION_LIMIT - 1 {
+            break;
+        }
+        cortado.push(c);
+    }
+    cortado + "…"
+}
+
+async fn send_admin_text(admin_group_id: i64, text: &str, responde_a: Option<i32>, ctx: &BotContext) -> Option<i32> {
+    let params = SendMessageParams::builder()
+        .chat_id(admin_group_id)
+        .text(text)
+        .maybe_reply_parameters(reply_to(responde_a))
+        .build();
+    call_admin(ctx, "sendMessage", ctx.bot.send_message(&params)).await.map(|message| message.message_id)
+}
+
+// Envia um álbum de provas, com a legenda no primeiro item. Devolve os IDs das mensagens, ou None se falhou
+async fn send_admin_album(
+    admin_group_id: i64,
+    album: &[(Midia, &str)],
+    caption: Option<&str>,
+    responde_a: Option<i32>,
+    ctx: &BotContext,
+) -> Option<Vec<i32>> {
+    let media: Vec<MediaGroupInputMedia> = album.iter()
+        .enumerate()
+        .map(|(i, (midia, file_id))| {
+            let file = FileUpload::String(file_id.to_string());
+            let caption = caption.filter(|_| i == 0).map(str::to_string);
+            match midia {
+                Midia::Video => InputMediaVideo::builder().media(file).maybe_caption(caption).build().into(),
+                Midia::Documento => InputMediaDocument::builder().media(file).maybe_caption(caption).build().into(),
+                _ => InputMediaPhoto::builder().media(file).maybe_caption(caption).build().into(),
+            }
+        })
+        .collect();
+    let params = SendMediaGroupParams::builder()
+        .chat_id(admin_group_id)
+        .media(media)
+        .maybe_reply_parameters(reply_to(responde_a))
+        .build();
+    let messages = call_admin(ctx, "sendMediaGroup", ctx.bot.send_media_group(&params)).await?;
+    Some(messages.iter().map(|message| message.message_id).collect())
+}
+
+// Reescreve o resumo de uma entrega no grupo de administração, com a situação atual no topo
+async fn update_admin_summary(entrega: &Entrega, ctx: &BotContext) {
+    let Some(&message_id) = entrega.mensagens_admin.first() else {
+        return;
+    };
+    if entrega.texto_admin.is_empty() {
+        return;
+    }
+    let mut situacao = match (entrega.status, &entrega.responsavel) {
+        (StatusEntrega::Pendente, Some(reserva)) => format!("🙋 Em análise por {}", reserva.admin),
+        (StatusEntrega::Pendente, None) => String::new(),
+        (StatusEntrega::Aprovada, _) => "✅ APROVADA".to_string(),
+        (StatusEntrega::Recusada, _) => "❌ RECUSADA".to_string(),
+        (StatusEntrega::Retirada, _) => "↩️ RETIRADA PELO JOGADOR".to_string(),
+    };
+    if let Some(avaliacao) = &entrega.avaliacao {
+        situacao.push_str(&format!(" por {}", avaliacao.admin));
+        if let Some(motivo) = &avaliacao.motivo {
+            situacao.push_str(&format!("\nMotivo: {}", motivo));
+        }
+    }
+    let admin_group_id = ctx.config.grupos.admin;
+    if let Some(recurso) = &entrega.recurso {
+        let link = recurso.mensagens_admin.first()
+            .and_then(|id| message_link(admin_group_id, *id))
+            .map(|link| format!(": {}", link))
+            .unwrap_or_default();
+        let linha = match &recurso.decisao {
+            None => format!("⚖️ Recurso em análise{}", link),
+            Some(decisao) if decisao.revertida => format!(
+                "⚖️ Recurso aceito; recusa anterior de {}: {}{}",
+                recurso.recusa.admin,
+                recurso.recusa.motivo.as_deref().unwrap_or("sem motivo"),
+                link
+            ),
+            Some(decisao) => format!("⚖️ Recurso negado por {}{}", decisao.admin, link),
+        };
+        situacao.push_str(&format!("\n{}", linha));
+    }
+    let mut texto = if situacao.is_empty() {
+        entrega.texto_admin.clone()
+    } else {
+        format!("{}\n\n{}", situacao, entrega.texto_admin)
+    };
+
+    if entrega.legenda_admin {
+        // A legenda cabia no envio; com o acréscimo, o fim do resumo pode precisar ser cortado
+        texto = cortar_legenda(&texto);
+        let params = EditMessageCaptionParams::builder()
+            .chat_id(admin_group_id)
+            .message_id(message_id)
+            .caption(texto)
+            .build();
+        call_admin(ctx, "editMessageCaption", ctx.bot.edit_message_caption(&params)).await;
+    } else {
+        let params = EditMessageTextParams::builder()
+            .chat_id(admin_group_id)
+            .message_id(message_id)
+            .text(texto)
+            .build();
+        call_admin(ctx, "editMessageText", ctx.bot.edit_message_text(&params)).await;
+    }
+}
+
+// As partes seguintes da entrega respondem à primeira, para ficarem no mesmo fio
+fn reply_to(message_id: Option<i32>) -> Option<ReplyParameters> {
+    message_id.map(|id| ReplyParameters::builder().message_id(id).allow_sending_without_reply(true).build())
+}
+
+// Faz uma chamada ao grupo de administração que precisa do resultado, fora da fila do outbox
+async fn call_admin<T>(
+    ctx: &BotContext,
+    method: &'static str,
+    call: impl Future<Output = Result<MethodResponse<T>, frankenstein::Error>>,
+) -> Option<T> {
+    let started = Instant::now();
+    let result = call.await;
+    ctx.metrics.observe_send_latency(started.elapsed());
+    match result {
+        Ok(response) => Some(response.result),
+        Err(e) => {
+            ctx.metrics.telegram_error(method);
+            error!(error = %e, method, "Falha na chamada ao grupo de administração");
+            None
+        }
+    }
+}
+
+async fn download_file(ctx: &BotContext, file_id: &str, midia: Midia, time: &str, user_id: i64) -> Result<Assinatura, BotError> {
+    let bot = &ctx.bot;
+    let get_file_params = GetFileParams::builder().file_id(file_id).build();
+    let file = bot.get_file(&get_file_params).await?.result;
+    let file_path = file.file_path.ok_or_else(|| BotError::Parse("arquivo sem file_path".to_string()))?;
+
+    let limite = ctx.config.limites.tamanho_maximo_bytes();
+    let grande_demais = || {
+        BotError::Recusa(format!(
+            "Este arquivo passa de {} MB, mais do que meus mensageiros conseguem carregar. Envie uma versão menor, guerreiro.",
+            ctx.config.limites.tamanho_maximo_mb
+        ))
+    };
+    if file.file_size.is_some_and(|size| size > limite) {
+        return Err(grande_demais());
+    }
+
+    let bytes = if Path::new(&file_path).is_absolute() {
+        // Um servidor local em modo --local devolve o caminho do arquivo no próprio disco
+        let metadata = fs::metadata(&file_path).map_err(|source| StorageError::Io { path: file_path.clone().into(), source })?;
+        if metadata.len() > limite {
+            return Err(grande_demais());
+        }
+        fs::read(&file_path).map_err(|source| StorageError::Io { path: file_path.clone().into(), source })?
+    } else {
+        // A conversão de reqwest::Error descarta a URL, que contém o token
+        let mut response = bot.client.get(ctx.config.file_download_url(&file_path))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())?;
+        // O tamanho informado pelo getFile é opcional; o limite vale também durante o download
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+            if bytes.len() as u64 > limite {
+                return Err(grande_demais());
+            }
+        }
+        bytes
+    };
+
+    // A extensão vem do conteúdo: um arquivo que não é a imagem ou o vídeo que diz ser não é gravado
+    let formato = provas::detectar_formato(&bytes)
+        .filter(|formato| formato.is_image() == midia.is_image())
+        .ok_or_else(|| {
+            BotError::Recusa("Não consigo ler este arquivo, guerreiro. Aceito imagens JPEG, PNG ou WebP e vídeos MP4 ou WebM.".to_string())
+        })?;
+
+    let dir_path = ctx.storage.entregas_dir(time);
+    let full_dir_path = ctx.storage.path(&dir_path);
+    fs::create_dir_all(&full_dir_path).map_err(|source| StorageError::Io { path: full_dir_path, source })?;
+
+    // O file_unique_id evita que duas fotos do mesmo usuário no mesmo milissegundo se sobrescrevam
+    let timestamp = Utc::now().timestamp_millis();
+    let file_extension = formato.extensao();
+    let new_file_path = format!("{}/{}_{}_{}.{}", dir_path, user_id, timestamp, file.file_unique_id, file_extension);
+
+    // Grava num .part e renomeia no fim, para que nunca fique uma imagem pela metade em entregas/
+    let full_path = ctx.storage.path(&new_file_path);
+    let partial_path = full_path.with_extension(format!("{}.part", file_extension));
+    let written = fs::File::create(&partial_path)
+        .and_then(|mut dest| dest.write_all(&bytes))
+        .and_then(|()| fs::rename(&partial_path, &full_path));
+    if let Err(source) = written {
+        let _ = fs::remove_file(&partial_path);
+        return Err(StorageError::Io { path: full_path, source }.into());
+    }
+
+    // Decodificar a imagem ocupa a CPU; fica fora das threads que atendem as conversas
+    let phash = if midia.is_image() {
+        tokio::task::spawn_blocking(move || provas::hash_perceptual(&bytes)).await.ok().flatten()
+    } else {
+        None
+    };
+    Ok(Assinatura { caminho: new_file_path, file_unique_id: file.file_unique_id, phash })
+}
+
+// Inscritos com time conhecido cujo nome atual não leva o emoji do time
+async fn emoji_report(ctx: &BotContext) -> Result<String, BotError> {
+    if TIMES.iter().all(|time| ctx.config.team_emoji(time).is_none()) {
+        return Ok("Nenhum emoji de time configurado ([emojis] ou SHU_EMOJI, WEI_EMOJI e WU_EMOJI).".to_string());
+    }
+
+    let inscritos = ctx.storage.read_inscritos()?;
+    let mut sem_emoji = Vec::new();
+    let mut indisponiveis = Vec::new();
+    let mut sem_time = 0;
+    for inscrito in &inscritos {
+        let (Some(user_id), Some(time)) = (inscrito.user_id, inscrito.time.as_deref()) else {
+            sem_time += 1;
+            continue;
+        };
+        let Some(emoji) = ctx.config.team_emoji(time) else {
+            continue;
+        };
+        // O nome atual vem do chat privado com o bot, que todo jogador já abriu para entregar
+        let params = GetChatParams::builder().chat_id(user_id).build();
+        let nome = match ctx.bot.get_chat(&params).await {
+            Ok(response) => {
+                let chat = response.result;
+                let nome = chat.first_name.unwrap_or_default();
+                match chat.last_name {
+                    Some(last_name) => format!("{} {}", nome, last_name),
+                    None => nome,
+                }
+            }
+            Err(e) => {
+                ctx.metrics.telegram_error("getChat");
+                warn!(error = %e, user_id, "Falha ao consultar o nome do inscrito");
+                indisponiveis.push(format!("- {} (@{}), {}", inscrito.nome, inscrito.user, time.to_uppercase()));
+                continue;
+            }
+        };
+        if !nome.contains(emoji) {
+            sem_emoji.push(format!("- {} (@{}), {} {}: \"{}\"", inscrito.nome, inscrito.user, time.to_uppercase(), emoji, nome));
+        }
+    }
+
+    let mut report = if sem_emoji.is_empty() {
+        "Todos os inscritos com time conhecido estão com o emoji do time no nome.\n".to_string()
+    } else {
+        format!("Inscritos sem o emoji do time no nome:\n\n{}\n", sem_emoji.join("\n"))
+    };
+    if !indisponiveis.is_empty() {
+        report.push_str(&format!("\nNão consegui consultar o nome atual de:\n{}\n", indisponiveis.join("\n")));
+    }
+    if sem_time > 0 {
+        report.push_str(&format!(
+            "\n{} inscritos ainda sem time conhecido; o time é registrado na primeira entrega de cada um.",
+            sem_time
+        ));
+    }
+    Ok(report)
+}
+
+// Comandos que o bot reconhece, usados para rotular as métricas
+const KNOWN_COMMANDS: &[&str] = &[
+    "/start", "/inscritos", "/entregarmissao", "/entregar", "/missoes", "/shu", "/wei", "/wu",
+    "/calendario", "/regras", "/premios", "/cancelar", "/verificaremojis", "/minhasentregas", "/retirarentrega", "/adicionarprovas",
+    "/aprovar", "/recusar", "/recorrer", "/enviar", "/fila", "/pegar", "/soltar", "/estatisticas", "/mudartime", "/add", "/remove", "/addsoldados", "/removesoldados",
+];
+
+// Comandos atendidos por handle_command_logic, que valem mesmo com uma conversa aberta
+const GLOBAL_COMMANDS: &[&str] = &[
+    "/start", "/inscritos", "/entregarmissao", "/missoes", "/shu", "/wei", "/wu", "/calendario", "/regras", "/premios",
+    "/minhasentregas", "/retirarentrega", "/adicionarprovas", "/recorrer",
+];
+
+fn is_known_command(command: &str) -> bool {
+    KNOWN_COMMANDS.contains(&command)
+}
+
+pub(crate) async fn set_menu_commands(bot: &Bot) -> Result<(), BotError> {
+    let commands = vec![
+        // BotCommand::builder()
+        //     .command("/inscricao")
+        //     .description("Jure lealdade e junte-se à minha nobre causa.")
+        //     .build(),
+        BotCommand::builder()
+            .command("/missoes")
+            .description("Consulte meus decretos e missões atuais.")
+            .build(),
+        BotCommand::builder()
+            .command("/entregarmissao")
+            .description("Apresente seus feitos para minha avaliação.")
+            .build(),
+        BotCommand::builder()
+            .command("/minhasentregas")
+            .description("Consulte suas entregas e a situação de cada uma.")
+            .build(),
+        BotCommand::builder()
+            .command("/cancelar")
+            .description("Desista da conversa em andamento.")
+            .build(),
+        BotCommand::builder()
+            .command("/calendario")
+            .description("Consulte o calendário do evento.")
+            .build(),
+        BotCommand::builder()
+            .command("/regras")
+            .description("Consulte as regras do evento.")
+            .build(),
+        BotCommand::builder()
+            .command("/premios")
+            .description("Consulte a premiação do evento.")
+            .build(),
+    ];
+
+    let params = SetMyCommandsParams::builder().commands(commands).build();
+    bot.set_my_commands(&params).await?;
+    info!("Menu de comandos configurado com sucesso.");
+    Ok(())
+}
+
+async fn handle_admin_commands(text: &str, message: &Message, ctx: &BotContext) -> Result<(), BotError> {
+    let chat_id = message.chat.id;
+    // Regex para /addsoldados e /removesoldados
+    let soldados_re = Regex::new(r"^/(add|remove)soldados\s+(shu|wei|wu)\s+(-?\d+)$").unwrap();
+    // Regex para /add e /remove de naipes; o /add pode terminar no número da entrega que cumpriu a missão
+    let naipe_re = Regex::new(r"^/(add|remove)\s+(shu|wei|wu)\s+(\d{1,2})\s+(pedra|papel|tesoura)(?:\s+#?(\d+))?$").unwrap();
+
+    if let Some(caps) = soldados_re.captures(text) {
+        let action = &caps[1];
+        let time = &caps[2];
+        let quantidade = caps[3].parse::<i32>().map_err(|e| BotError::Parse(format!("quantidade de soldados: {}", e)))?;
+
+        let db = ctx.storage.update_time_db(time, |db| {
+            let soldados = if action == "add" {
+                db.soldados.checked_add(quantidade)
+            } else {
+                db.soldados.checked_sub(quantidade)
+            };
+            db.soldados = soldados.ok_or_else(|| BotError::Parse("quantidade de soldados fora do limite".to_string()))?;
+            Ok(())
+        })?;
+
+        send_message(chat_id, &format!("Soldados do time {} atualizados. Total: {}", time.to_uppercase(), db.soldados), ctx).await;
+        // Send notification to team group
+        if let Some(team_group_id) = ctx.config.team_group_id(time) {
+            send_message(team_group_id, &format!("Atenção, nobres guerreiros de {}! Seus soldados foram atualizados. Contamos agora com {} bravos combatentes em nossas fileiras!", time.to_uppercase(), db.soldados), ctx).await;
+        }
+        return Ok(());
+    }
+
+    if let Some(caps) = naipe_re.captures(text) {
+        let action = &caps[1];
+        let time = &caps[2];
+        let naipe_idx = caps[3].parse::<usize>().map_err(|e| BotError::Parse(format!("naipe: {}", e)))?;
+        let missao = &caps[4];
+
+        if !(1..=22).contains(&naipe_idx) {
+            send_message(chat_id, "Naipe inválido. Deve ser entre 1 e 22.", ctx).await;
+            return Ok(());
+        }
+
+        // Com o número, o crédito é a aprovação da entrega: o jogador é avisado e nomeado no grupo do time
+        if let Some(numero) = caps.get(5) {
+            if action != "add" {
+                return Err(BotError::Recusa("O /remove desfaz só o crédito; use-o sem o número da entrega.".to_string()));
+            }
+            let id = numero.as_str().parse::<u32>().map_err(|e| BotError::Parse(format!("número da entrega: {}", e)))?;
+            let missao = MissaoCumprida { naipe: naipe_idx, tipo: missao.to_string() };
+            return avaliar_entrega(id, "", true, Some((time, missao)), message, ctx).await;
+        }
+
+        let delta = if action == "add" { 1 } else { -1 };
+        ctx.storage.creditar_missao(time, naipe_idx, missao, delta)?;
+
+        let mut response = format!("Missão {} do naipe {} para o time {} atualizada.", missao, naipe_idx, time.to_uppercase());
+        // O crédito manual não muda a situação das entregas: aprovar depois uma delas creditaria a missão outra vez
+        if action == "add" {
+            let pendentes: Vec<String> = ctx.storage.read_entregas(time)?
+                .iter()
+                .filter(|e| e.id > 0 && e.status == StatusEntrega::Pendente)
+                .filter(|e| e.missao.as_ref().is_some_and(|m| m.naipe == naipe_idx && m.tipo.eq_ignore_ascii_case(missao)))
+                .map(|e| format!("Nº {}", e.id))
+                .collect();
+            if !pendentes.is_empty() {
+                response.push_str(&format!(
+                    "\n\n⚠️ Entregas desta missão aguardando avaliação: {}. Se o crédito era de uma delas, desfaça-o com /remove e credite-a com /add {} {} {} e o número dela, que avisa o jogador; aprová-la agora creditaria a missão de novo.",
+                    pendentes.join(", "),
+                    time,
+                    naipe_idx,
+                    missao
+                ));
+            }
+        }
+        send_message(chat_id, &response, ctx).await;
+        // Send notification to team group
+        if let Some(team_group_id) = ctx.config.team_group_id(time) {
+            send_message(team_group_id, &format!("Atenção, guerreiros de {}! A missão do naipe {} ({}) foi atualizada em seus registros. Que a glória os acompanhe!", time.to_uppercase(), naipe_idx, missao.to_uppercase()), ctx).await;
+        }
+        return Ok(());
+    }
+
+    // Se nenhum regex corresponder
+    send_message(chat_id, "Comando de admin não reconhecido ou formato inválido.", ctx).await;
+    Ok(())
+}
+
+async fn send_team_db(chat_id: i64, team_name: &str, ctx: &BotContext) -> Result<(), BotError> {
+    let response = format_team_db(&ctx.storage, team_name)?;
+    send_message(chat_id, &response, ctx).await;
+    Ok(())
+}
@@ -0,0 +1,26 @@
+// Yuan Shao Bot: tipos do evento, arquivos de dados e o bot do Telegram que registra as entregas.
+// O binário yuanshao-bot é só a linha de comando; outras ferramentas (placar, testes) usam esta biblioteca
+pub mod bot;
+pub mod cli;
+pub mod config;
+pub mod conversa;
+pub mod domain;
+pub mod error;
+pub mod format;
+pub mod handlers;
+pub mod logging;
+pub mod metrics;
+mod outbox;
+pub mod parsing;
+pub mod provas;
+pub mod replay;
+pub mod resumo;
+pub mod revisao;
+pub mod storage;
+
+pub use bot::{check_config, run_bot, BotContext};
+pub use config::Config;
+pub use conversa::UserState;
+pub use domain::{Entrega, Inscrito, Missao, Naipe, TimeDB, TIMES};
+pub use error::{BotError, StorageError};
+pub use storage::Storage;
@@ -0,0 +1,150 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::io::{self, Write};
+use std::sync::{Arc, OnceLock};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+// Formato da saída de log
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Texto,
+    Json,
+}
+
+impl LogFormat {
+    pub fn parse(value: &str) -> Option<LogFormat> {
+        match value.trim().to_lowercase().as_str() {
+            "texto" | "text" => Some(LogFormat::Texto),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+// Configura o subscriber global. `nivel` segue a sintaxe do RUST_LOG (ex.: "info" ou "yuanshao_bot=debug"),
+// e `secrets` são valores que nunca podem aparecer na saída, como o token do bot
+pub fn init(format: LogFormat, nivel: &str, secrets: Vec<String>) {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(nivel))
+        .unwrap_or_else(|_| EnvFilter::new("info"));
+    let writer = RedactingStdout {
+        redactor: Arc::new(Redactor::new(secrets)),
+    };
+
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_writer(writer);
+    let result = match format {
+        LogFormat::Texto => builder.try_init(),
+        LogFormat::Json => builder.json().flatten_event(true).with_current_span(true).try_init(),
+    };
+    if let Err(e) = result {
+        eprintln!("Falha ao configurar os logs: {}", e);
+    }
+}
+
+// Substitui segredos conhecidos e qualquer coisa com cara de token de bot
+struct Redactor {
+    secrets: Vec<String>,
+}
+
+// Nas URLs o token vem colado ao "bot" (/bot<token>/método), sem fronteira de palavra antes dele
+fn token_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"(\b|/bot)\d{6,}:[A-Za-z0-9_-]{30,}").unwrap())
+}
+
+impl Redactor {
+    fn new(secrets: Vec<String>) -> Self {
+        Redactor {
+            secrets: secrets.into_iter().filter(|s| !s.is_empty()).collect(),
+        }
+    }
+
+    fn redact(&self, line: &str) -> String {
+        let mut line = line.to_string();
+        for secret in &self.secrets {
+            line = line.replace(secret.as_str(), "<redacted>");
+        }
+        token_pattern().replace_all(&line, "${1}<redacted>").into_owned()
+    }
+}
+
+#[derive(Clone)]
+struct RedactingStdout {
+    redactor: Arc<Redactor>,
+}
+
+// Acumula um evento inteiro e só o escreve, já sem segredos, quando é descartado
+struct RedactingLine {
+    redactor: Arc<Redactor>,
+    buffer: Vec<u8>,
+}
+
+impl<'a> MakeWriter<'a> for RedactingStdout {
+    type Writer = RedactingLine;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingLine {
+            redactor: Arc::clone(&self.redactor),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl Write for RedactingLine {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RedactingLine {
+    fn drop(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let line = self.redactor.redact(&String::from_utf8_lossy(&self.buffer));
+        let _ = io::stdout().lock().write_all(line.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "1234567890:AAHdqTcvCH1vGWJxfSeofSAs0K5PALDsaw4";
+
+    #[test]
+    fn bot_tokens_are_redacted_alone_and_inside_api_urls() {
+        let redactor = Redactor::new(Vec::new());
+        assert_eq!(redactor.redact(&format!("token {}", TOKEN)), "token <redacted>");
+        assert_eq!(
+            redactor.redact(&format!("https://api.telegram.org/bot{}/sendMessage", TOKEN)),
+            "https://api.telegram.org/bot<redacted>/sendMessage"
+        );
+        assert_eq!(
+            redactor.redact(&format!("http://127.0.0.1:8081/file/bot{}/photos/file_1.jpg", TOKEN)),
+            "http://127.0.0.1:8081/file/bot<redacted>/photos/file_1.jpg"
+        );
+    }
+
+    #[test]
+    fn configured_secrets_are_redacted_even_when_short() {
+        let redactor = Redactor::new(vec!["42:curto".to_string(), String::new()]);
+        assert_eq!(redactor.redact("/bot42:curto/getMe"), "/bot<redacted>/getMe");
+    }
+
+    #[test]
+    fn chat_ids_and_short_pairs_are_kept() {
+        let redactor = Redactor::new(Vec::new());
+        for line in ["chat_id=-1001234567890", "user_id=987654321", "123:abc", "1234567:curto"] {
+            assert_eq!(redactor.redact(line), line);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::bot::BotContext;
+
+// Limites (em segundos) do histograma de latência de envio
+const SEND_LATENCY_BUCKETS: [f64; 8] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+// Sem um get_updates bem-sucedido por esse tempo, o /healthz passa a responder 503
+const HEALTHY_POLL_WINDOW: Duration = Duration::from_secs(120);
+
+// Contadores do bot, expostos no formato de texto do Prometheus
+#[derive(Default)]
+pub struct Metrics {
+    updates_processed: AtomicU64,
+    commands: Mutex<BTreeMap<String, u64>>,
+    deliveries: Mutex<BTreeMap<String, u64>>,
+    telegram_errors: Mutex<BTreeMap<String, u64>>,
+    send_latency: Mutex<Histogram>,
+    // Segundos Unix; 0 enquanto nenhum get_updates deu certo
+    last_successful_poll: AtomicI64,
+}
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; SEND_LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+fn increment(map: &Mutex<BTreeMap<String, u64>>, label: &str) {
+    let mut map = map.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *map.entry(label.to_string()).or_default() += 1;
+}
+
+impl Metrics {
+    pub fn update_processed(&self) {
+        self.updates_processed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn command(&self, name: &str) {
+        increment(&self.commands, name);
+    }
+
+    pub fn delivery(&self, time: &str) {
+        increment(&self.deliveries, time);
+    }
+
+    pub fn telegram_error(&self, method: &str) {
+        increment(&self.telegram_errors, method);
+    }
+
+    pub fn observe_send_latency(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let mut histogram = self.send_latency.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        for (bucket, limit) in histogram.buckets.iter_mut().zip(SEND_LATENCY_BUCKETS) {
+            if seconds <= limit {
+                *bucket += 1;
+            }
+        }
+        histogram.count += 1;
+        histogram.sum += seconds;
+    }
+
+    pub fn poll_succeeded(&self) {
+        self.last_successful_poll.store(Utc::now().timestamp(), Ordering::Relaxed);
+    }
+
+    pub fn last_successful_poll(&self) -> Option<DateTime<Utc>> {
+        match self.last_successful_poll.load(Ordering::Relaxed) {
+            0 => None,
+            seconds => DateTime::from_timestamp(seconds, 0),
+        }
+    }
+
+    fn render(&self, active_states: usize) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# HELP yuanshao_updates_processed_total Mensagens recebidas e processadas.");
+        let _ = writeln!(out, "# TYPE yuanshao_updates_processed_total counter");
+        let _ = writeln!(out, "yuanshao_updates_processed_total {}", self.updates_processed.load(Ordering::Relaxed));
+
+        render_labeled(&mut out, "yuanshao_commands_total", "Comandos recebidos, por nome.", "command", &self.commands);
+        render_labeled(&mut out, "yuanshao_deliveries_total", "Entregas registradas, por time.", "time", &self.deliveries);
+        render_labeled(&mut out, "yuanshao_telegram_errors_total", "Falhas em chamadas à API do Telegram, por método.", "method", &self.telegram_errors);
+
+        let histogram = self.send_latency.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let _ = writeln!(out, "# HELP yuanshao_send_latency_seconds Latência das chamadas de envio ao Telegram.");
+        let _ = writeln!(out, "# TYPE yuanshao_send_latency_seconds histogram");
+        for (count, limit) in histogram.buckets.iter().zip(SEND_LATENCY_BUCKETS) {
+            let _ = writeln!(out, "yuanshao_send_latency_seconds_bucket{{le=\"{}\"}} {}", limit, count);
+        }
+        let _ = writeln!(out, "yuanshao_send_latency_seconds_bucket{{le=\"+Inf\"}} {}", histogram.count);
+        let _ = writeln!(out, "yuanshao_send_latency_seconds_sum {}", histogram.sum);
+        let _ = writeln!(out, "yuanshao_send_latency_seconds_count {}", histogram.count);
+        drop(histogram);
+
+        let _ = writeln!(out, "# HELP yuanshao_active_conversations Usuários com uma conversa em andamento.");
+        let _ = writeln!(out, "# TYPE yuanshao_active_conversations gauge");
+        let _ = writeln!(out, "yuanshao_active_conversations {}", active_states);
+
+        let _ = writeln!(out, "# HELP yuanshao_last_successful_poll_timestamp_seconds Momento do último get_updates bem-sucedido.");
+        let _ = writeln!(out, "# TYPE yuanshao_last_successful_poll_timestamp_seconds gauge");
+        let _ = writeln!(out, "yuanshao_last_successful_poll_timestamp_seconds {}", self.last_successful_poll.load(Ordering::Relaxed));
+
+        out
+    }
+}
+
+fn render_labeled(out: &mut String, name: &str, help: &str, label: &str, values: &Mutex<BTreeMap<String, u64>>) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let values = values.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for (value, count) in values.iter() {
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+        let _ = writeln!(out, "{}{{{}=\"{}\"}} {}", name, label, value, count);
+    }
+}
+
+// Sobe o servidor HTTP de /metrics e /healthz em segundo plano
+pub async fn serve(address: &str, ctx: Arc<BotContext>) {
+    let listener = match tokio::net::TcpListener::bind(address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(address, error = %e, "Falha ao abrir o endpoint de métricas");
+            return;
+        }
+    };
+    info!(address, "Endpoint de métricas disponível");
+
+    let app = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .route("/healthz", get(health_handler))
+        .with_state(ctx);
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            error!(error = %e, "O servidor de métricas parou");
+        }
+    });
+}
+
+async fn metrics_handler(State(ctx): State<Arc<BotContext>>) -> impl IntoResponse {
+    let active_states = ctx.user_states.active_count().await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        ctx.metrics.render(active_states),
+    )
+}
+
+async fn health_handler(State(ctx): State<Arc<BotContext>>) -> impl IntoResponse {
+    let last_poll = ctx.metrics.last_successful_poll();
+    let seconds_since = last_poll.map(|at| (Utc::now() - at).num_seconds());
+    let healthy = seconds_since.is_some_and(|seconds| seconds <= HEALTHY_POLL_WINDOW.as_secs() as i64);
+
+    let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let body = json!({
+        "status": if healthy { "ok" } else { "stale" },
+        "last_successful_get_updates": last_poll.map(|at| at.to_rfc3339()),
+        "seconds_since_last_successful_get_updates": seconds_since,
+    });
+    (status, Json(body))
+}
@@ -0,0 +1,153 @@
+use frankenstein::methods::SendMessageParams;
+use frankenstein::AsyncTelegramApi;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+use tokio::sync::{mpsc, Semaphore};
+use tokio::time::{sleep, timeout, Duration, Instant};
+use tokio_util::task::TaskTracker;
+use tracing::{error, warn};
+
+use crate::bot::BotContext;
+
+// Tentativas para uma mensagem que o Telegram mandou esperar (HTTP 429)
+const MAX_ATTEMPTS: u32 = 3;
+// Envios simultâneos, somando todos os chats
+const MAX_IN_FLIGHT: usize = 16;
+// Tempo que a fila de um chat fica ociosa antes de ser descartada
+const QUEUE_IDLE: Duration = Duration::from_secs(30);
+
+// Filas de mensagens de texto a enviar, uma por chat: cada chat recebe as suas na ordem de chegada, e
+// uma espera pedida pelo Telegram num chat movimentado não atrasa os outros. No encerramento as filas
+// são esvaziadas antes de o processo sair
+pub struct Outbox {
+    context: OnceLock<Weak<BotContext>>,
+    queues: Mutex<HashMap<i64, mpsc::UnboundedSender<String>>>,
+    workers: TaskTracker,
+    in_flight: Semaphore,
+    // Envia cada mensagem na hora, sem fila; usado pelo replay
+    immediate: AtomicBool,
+}
+
+impl Default for Outbox {
+    fn default() -> Self {
+        Outbox {
+            context: OnceLock::new(),
+            queues: Mutex::new(HashMap::new()),
+            workers: TaskTracker::new(),
+            in_flight: Semaphore::new(MAX_IN_FLIGHT),
+            immediate: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Outbox {
+    pub fn start(ctx: &Arc<BotContext>) {
+        let _ = ctx.outbox.context.set(Arc::downgrade(ctx));
+    }
+
+    // Sem as filas, as mensagens saem na ordem em que o bot as produz, intercaladas com as chamadas
+    // diretas ao grupo de administração, e a saída do replay não depende do agendamento das tarefas
+    pub fn send_immediately(&self) {
+        self.immediate.store(true, Ordering::Relaxed);
+    }
+
+    // Envia a mensagem pela fila do chat ou, no modo imediato, antes de retornar
+    pub async fn push(&self, ctx: &BotContext, chat_id: i64, text: &str) {
+        if self.immediate.load(Ordering::Relaxed) {
+            send(ctx, chat_id, text).await;
+        } else {
+            self.enqueue(chat_id, text);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<i64, mpsc::UnboundedSender<String>>> {
+        self.queues.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Coloca a mensagem na fila do chat, criando a fila (e seu worker) se necessário
+    pub fn enqueue(&self, chat_id: i64, text: &str) {
+        let mut queues = self.lock();
+        let ctx = self.context.get().and_then(Weak::upgrade);
+        let Some(ctx) = ctx.filter(|_| !self.workers.is_closed()) else {
+            warn!(chat_id, "Fila de saída fechada; mensagem descartada");
+            return;
+        };
+        let text = match queues.get(&chat_id) {
+            Some(sender) => match sender.send(text.to_string()) {
+                Ok(()) => return,
+                Err(mpsc::error::SendError(text)) => text,
+            },
+            None => text.to_string(),
+        };
+        let (sender, receiver) = mpsc::unbounded_channel();
+        sender.send(text).expect("receiver is alive");
+        queues.insert(chat_id, sender);
+        self.workers.spawn(run(ctx, chat_id, receiver));
+    }
+
+    // Fecha as filas e espera os workers enviarem o que já estava nelas
+    pub async fn flush(&self) {
+        {
+            let mut queues = self.lock();
+            self.workers.close();
+            queues.clear();
+        }
+        self.workers.wait().await;
+    }
+}
+
+async fn run(ctx: Arc<BotContext>, chat_id: i64, mut receiver: mpsc::UnboundedReceiver<String>) {
+    loop {
+        match timeout(QUEUE_IDLE, receiver.recv()).await {
+            Ok(Some(text)) => send(&ctx, chat_id, &text).await,
+            Ok(None) => return,
+            Err(_) => {
+                // Com o mapa travado ninguém consegue enfileirar, então é seguro encerrar se a fila estiver vazia
+                let mut queues = ctx.outbox.lock();
+                if receiver.is_empty() {
+                    queues.remove(&chat_id);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+async fn send(ctx: &BotContext, chat_id: i64, text: &str) {
+    let params = SendMessageParams::builder()
+        .chat_id(chat_id)
+        .text(text)
+        .build();
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        // A vaga é devolvida antes de uma eventual espera, para não segurar os envios dos outros chats
+        let result = {
+            let _vaga = ctx.outbox.in_flight.acquire().await.expect("semaphore is never closed");
+            let started = Instant::now();
+            let result = ctx.bot.send_message(&params).await;
+            ctx.metrics.observe_send_latency(started.elapsed());
+            result
+        };
+        let err = match result {
+            Ok(_) => return,
+            Err(err) => err,
+        };
+        ctx.metrics.telegram_error("sendMessage");
+
+        let retry_after = match &err {
+            frankenstein::Error::Api(response) => response.parameters.as_ref().and_then(|p| p.retry_after),
+            _ => None,
+        };
+        match retry_after {
+            Some(seconds) if attempt < MAX_ATTEMPTS => {
+                warn!(chat_id, retry_after = seconds, "Telegram pediu para aguardar antes de enviar");
+                sleep(Duration::from_secs(seconds.into())).await;
+            }
+            _ => {
+                error!(chat_id, error = %err, "Falha ao enviar mensagem");
+                return;
+            }
+        }
+    }
+}
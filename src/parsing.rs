@@ -0,0 +1,75 @@
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::domain::{MissaoCumprida, NAIPES};
+
+// New struct to hold parsed mission emojis
+#[derive(Debug, Clone, Default)]
+pub struct MissionEmojis {
+    pub emojis: HashMap<usize, HashMap<String, String>>, // Naipe index -> (Type -> Emoji)
+}
+
+pub fn parse_missoes_emojis(missoes_text: &str) -> MissionEmojis {
+    let mut mission_emojis = MissionEmojis { emojis: HashMap::new() };
+    let naipe_sections: Vec<&str> = missoes_text.split("Naipe ").collect();
+    let naipe_num_re = Regex::new(r"^(\d{1,2})").unwrap(); // Changed to \d{1,2} to match 1 or 2 digits
+    let mission_type_re = Regex::new(r"●\s*([^\s]+)\s*.+?\s*\((Pedra|Papel|Tesoura)\)").unwrap();
+
+    for section in naipe_sections.iter().skip(1) { // Skip the first part before "Naipe 01"
+        if let Some(first_line_end) = section.find('\n') {
+            let first_line = &section[..first_line_end];
+            let rest_of_section = &section[first_line_end..];
+
+            if let Some(caps) = naipe_num_re.captures(first_line) {
+                let naipe_index = caps[1].parse::<usize>().unwrap();
+                let mut current_naipe_emojis = HashMap::new();
+
+                for mission_caps in mission_type_re.captures_iter(rest_of_section) {
+                    let emoji = mission_caps[1].trim().to_string();
+                    let mission_type = mission_caps[2].to_string();
+                    current_naipe_emojis.insert(mission_type, emoji);
+                }
+                mission_emojis.emojis.insert(naipe_index, current_naipe_emojis);
+            }
+        }
+    }
+    mission_emojis
+}
+
+// Missão informada na conversa de entrega, como "3 pedra", "naipe 03 Papel" ou "12 - tesoura"
+pub fn parse_missao_cumprida(text: &str) -> Option<MissaoCumprida> {
+    let missao_re = Regex::new(r"(?i)^(?:naipe\s*)?(\d{1,2})\s*[-,/]?\s*(pedra|papel|tesoura)$").unwrap();
+    let caps = missao_re.captures(text.trim())?;
+    let naipe = caps[1].parse::<usize>().ok().filter(|n| (1..=NAIPES).contains(n))?;
+    Some(MissaoCumprida { naipe, tipo: caps[2].to_lowercase() })
+}
+
+// Links para a mensagem de fim de partida, normalizados como https://t.me/<grupo>/<mensagem>.
+// Aceita grupos públicos (t.me/grupo/123), privados (t.me/c/100123/456) e tópicos (t.me/grupo/7/123)
+pub fn parse_links_partida(text: &str) -> Vec<String> {
+    let link_re = Regex::new(r"(?i)(?:https?://)?(?:www\.)?(?:t|telegram)\.me/(c/\d+|[a-z][a-z0-9_]{3,31})/(?:\d+/)?(\d+)").unwrap();
+    link_re
+        .captures_iter(text)
+        .map(|caps| format!("https://t.me/{}/{}", caps[1].to_lowercase(), &caps[2]))
+        .collect()
+}
+
+// true se o texto cita algum link do Telegram que não aponta para uma mensagem
+pub fn has_malformed_link(text: &str) -> bool {
+    let any_link_re = Regex::new(r"(?i)(?:t|telegram)\.me/").unwrap();
+    any_link_re.find_iter(text).count() > parse_links_partida(text).len()
+}
+
+// Descrição de uma missão no texto das missões, sem o emoji e sem o tipo entre parênteses
+pub fn parse_missao_descricao(missoes_text: &str, missao: &MissaoCumprida) -> Option<String> {
+    let naipe_num_re = Regex::new(r"^(\d{1,2})").unwrap();
+    let mission_re = Regex::new(r"(?i)●\s*(.+?)\s*\((Pedra|Papel|Tesoura)\)").unwrap();
+
+    let section = missoes_text
+        .split("Naipe ")
+        .skip(1)
+        .find(|section| naipe_num_re.captures(section).and_then(|caps| caps[1].parse::<usize>().ok()) == Some(missao.naipe))?;
+    let caps = mission_re.captures_iter(section).find(|caps| caps[2].eq_ignore_ascii_case(&missao.tipo))?;
+    // O emoji da missão às vezes vem colado ao texto, como em "🎎🕴Como mestre"
+    Some(caps[1].trim_start_matches(|c: char| !c.is_alphabetic()).trim().to_string())
+}
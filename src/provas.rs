@@ -0,0 +1,249 @@
+// Checagens dos arquivos de prova: o formato real, pelos primeiros bytes, e as provas repetidas, seja o
+// mesmo arquivo do Telegram (file_unique_id) ou uma imagem quase igual (hash perceptual), já registrados
+// ou enviados na mesma entrega
+use image::imageops::FilterType;
+
+use crate::domain::{Assinatura, Entrega, StatusEntrega, TIMES};
+use crate::error::BotError;
+use crate::storage::Storage;
+
+// Bits diferentes, entre 64, a partir dos quais duas imagens deixam de ser consideradas a mesma
+const LIMIAR: u32 = 6;
+
+// dHash: a imagem reduzida a 9x8 tons de cinza, um bit por par de pixels vizinhos na horizontal.
+// Devolve None se os bytes não forem uma imagem que saibamos decodificar
+pub fn hash_perceptual(bytes: &[u8]) -> Option<u64> {
+    let imagem = image::load_from_memory(bytes).ok()?;
+    let reduzida = imagem.grayscale().resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if reduzida.get_pixel(x, y)[0] < reduzida.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    Some(hash)
+}
+
+// Uma prova da nova entrega que já apareceu numa entrega registrada ou mais cedo na própria entrega
+#[derive(Debug, Clone)]
+pub struct Repetida {
+    // Posição da prova entre os arquivos da nova entrega, a partir de 1
+    pub prova: usize,
+    // Mesmo arquivo do Telegram; se false, apenas uma imagem muito parecida
+    pub exata: bool,
+    pub anterior: Anterior,
+}
+
+// Onde a prova repetida apareceu primeiro
+#[derive(Debug, Clone)]
+pub enum Anterior {
+    Entrega(Box<Entrega>),
+    // Posição, a partir de 1, de um arquivo anterior da mesma entrega
+    MesmaEntrega(usize),
+}
+
+// Compara as assinaturas de uma nova entrega com as de todas as entregas registradas dos três times e,
+// não havendo repetição nelas, com as dos arquivos anteriores da própria entrega. As retiradas ficam de
+// fora: o jogador pode reapresentar as mesmas provas numa nova entrega
+pub fn procurar_repetidas(storage: &Storage, assinaturas: &[Assinatura]) -> Result<Vec<Repetida>, BotError> {
+    let mut registradas = Vec::new();
+    for time in TIMES {
+        registradas.extend(storage.read_entregas(time)?.into_iter().filter(|e| e.status != StatusEntrega::Retirada));
+    }
+    Ok(comparar(&registradas, assinaturas))
+}
+
+fn comparar(registradas: &[Entrega], assinaturas: &[Assinatura]) -> Vec<Repetida> {
+    let mut repetidas = Vec::new();
+    for (i, nova) in assinaturas.iter().enumerate() {
+        let encontrada = registradas
+            .iter()
+            .find_map(|entrega| {
+                entrega.assinaturas.iter().find_map(|antiga| semelhanca(nova, antiga)).map(|exata| (exata, Anterior::Entrega(Box::new(entrega.clone()))))
+            })
+            .or_else(|| {
+                assinaturas[..i].iter().enumerate().find_map(|(j, antiga)| semelhanca(nova, antiga).map(|exata| (exata, Anterior::MesmaEntrega(j + 1))))
+            });
+        if let Some((exata, anterior)) = encontrada {
+            repetidas.push(Repetida { prova: i + 1, exata, anterior });
+        }
+    }
+    repetidas
+}
+
+// Some(true) para o mesmo arquivo, Some(false) para imagens quase iguais, None para provas distintas
+fn semelhanca(nova: &Assinatura, antiga: &Assinatura) -> Option<bool> {
+    if !nova.file_unique_id.is_empty() && nova.file_unique_id == antiga.file_unique_id {
+        return Some(true);
+    }
+    match (nova.phash, antiga.phash) {
+        (Some(a), Some(b)) if (a ^ b).count_ones() <= LIMIAR => Some(false),
+        _ => None,
+    }
+}
+
+// Formatos aceitos como prova, reconhecidos pelos primeiros bytes do arquivo e não pela extensão
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Formato {
+    Jpeg,
+    Png,
+    Webp,
+    Mp4,
+    Webm,
+}
+
+impl Formato {
+    pub fn extensao(self) -> &'static str {
+        match self {
+            Formato::Jpeg => "jpg",
+            Formato::Png => "png",
+            Formato::Webp => "webp",
+            Formato::Mp4 => "mp4",
+            Formato::Webm => "webm",
+        }
+    }
+
+    pub fn is_image(self) -> bool {
+        matches!(self, Formato::Jpeg | Formato::Png | Formato::Webp)
+    }
+}
+
+pub fn detectar_formato(bytes: &[u8]) -> Option<Formato> {
+    match bytes {
+        [0xFF, 0xD8, 0xFF, ..] => Some(Formato::Jpeg),
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some(Formato::Png),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(Formato::Webp),
+        // MP4 e MOV: caixa "ftyp" logo depois do tamanho
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => Some(Formato::Mp4),
+        // WebM e Matroska: cabeçalho EBML
+        [0x1A, 0x45, 0xDF, 0xA3, ..] => Some(Formato::Webm),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_come_from_the_first_bytes() {
+        assert_eq!(detectar_formato(b"\xFF\xD8\xFF\xE0\x00\x10JFIF"), Some(Formato::Jpeg));
+        assert_eq!(detectar_formato(b"\x89PNG\r\n\x1A\n\x00\x00\x00\x0DIHDR"), Some(Formato::Png));
+        assert_eq!(detectar_formato(b"RIFF\x24\x00\x00\x00WEBPVP8 "), Some(Formato::Webp));
+        assert_eq!(detectar_formato(b"\x00\x00\x00\x18ftypmp42\x00\x00\x00\x00"), Some(Formato::Mp4));
+        assert_eq!(detectar_formato(b"\x00\x00\x00\x14ftypqt  "), Some(Formato::Mp4));
+        assert_eq!(detectar_formato(b"\x1A\x45\xDF\xA3\x9F\x42\x86\x81"), Some(Formato::Webm));
+    }
+
+    #[test]
+    fn truncated_headers_are_not_accepted() {
+        for bytes in [&b""[..], b"\xFF\xD8", b"\x89PNG\r\n", b"RIFF\x24\x00\x00\x00WEB", b"\x00\x00\x00\x18fty", b"\x1A\x45\xDF"] {
+            assert_eq!(detectar_formato(bytes), None, "{:?}", bytes);
+        }
+    }
+
+    #[test]
+    fn a_renamed_file_is_judged_by_its_content() {
+        // Um PDF, um executável e um texto chamados de foto.jpg continuam não sendo imagens
+        for bytes in [&b"%PDF-1.7\n%\xE2\xE3\xCF\xD3"[..], b"MZ\x90\x00\x03\x00\x00\x00", b"<html><body>foto</body></html>"] {
+            assert_eq!(detectar_formato(bytes), None, "{:?}", bytes);
+        }
+        // E um vídeo enviado como foto é reconhecido como vídeo, que não vale como imagem
+        assert!(!detectar_formato(b"\x00\x00\x00\x18ftypisom").unwrap().is_image());
+    }
+
+    // Uma cena lisa de 96x64 pixels, com ondas de frequência dada, para que o dHash tenha o que comparar
+    fn cena(ondas: f32, clarear: u8) -> image::RgbImage {
+        image::RgbImage::from_fn(96, 64, |x, y| {
+            let fx = x as f32 / 96.0 * std::f32::consts::TAU * ondas;
+            let fy = y as f32 / 64.0 * std::f32::consts::PI;
+            let tom = (128.0 + 100.0 * fx.sin() * fy.cos()).clamp(0.0, 255.0) as u8;
+            image::Rgb([tom.saturating_add(clarear), tom / 2, 255 - tom])
+        })
+    }
+
+    fn png(imagem: &image::RgbImage) -> Vec<u8> {
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        imagem.write_to(&mut bytes, image::ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    fn jpeg(imagem: &image::RgbImage, qualidade: u8) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, qualidade).encode_image(imagem).unwrap();
+        bytes
+    }
+
+    fn assinatura(file_unique_id: &str, bytes: &[u8]) -> Assinatura {
+        Assinatura { caminho: String::new(), file_unique_id: file_unique_id.to_string(), phash: hash_perceptual(bytes) }
+    }
+
+    fn distancia(a: &[u8], b: &[u8]) -> u32 {
+        (hash_perceptual(a).unwrap() ^ hash_perceptual(b).unwrap()).count_ones()
+    }
+
+    #[test]
+    fn recompressed_resized_or_brightened_copies_stay_within_the_limit() {
+        let original = png(&cena(1.25, 0));
+        let recomprimida = jpeg(&cena(1.25, 0), 40);
+        let reduzida = png(&image::imageops::resize(&cena(1.25, 0), 48, 32, FilterType::Triangle));
+        let clareada = jpeg(&cena(1.25, 12), 75);
+        for copia in [&recomprimida, &reduzida, &clareada] {
+            assert!(distancia(&original, copia) <= LIMIAR, "distância {}", distancia(&original, copia));
+            assert_eq!(semelhanca(&assinatura("b", copia), &assinatura("a", &original)), Some(false));
+        }
+    }
+
+    #[test]
+    fn different_images_are_not_taken_for_copies() {
+        let original = png(&cena(1.25, 0));
+        let espelhada = png(&image::imageops::flip_horizontal(&cena(1.25, 0)));
+        let outra = png(&cena(3.5, 0));
+        for diferente in [&espelhada, &outra] {
+            assert!(distancia(&original, diferente) > LIMIAR, "distância {}", distancia(&original, diferente));
+            assert_eq!(semelhanca(&assinatura("b", diferente), &assinatura("a", &original)), None);
+        }
+        // Bytes que não são imagem não têm hash, e só o mesmo arquivo do Telegram os iguala
+        assert_eq!(hash_perceptual(b"\x00\x00\x00\x18ftypmp42"), None);
+        assert_eq!(semelhanca(&assinatura("v", b"video"), &assinatura("v", b"video")), Some(true));
+        assert_eq!(semelhanca(&assinatura("", b"video"), &assinatura("", b"video")), None);
+    }
+
+    #[test]
+    fn repeats_inside_the_same_delivery_are_flagged() {
+        let original = png(&cena(1.25, 0));
+        let novas = [
+            assinatura("a", &original),
+            assinatura("b", &png(&cena(3.5, 0))),
+            assinatura("a", &original),
+            assinatura("c", &jpeg(&cena(1.25, 0), 40)),
+        ];
+        let repetidas = comparar(&[], &novas);
+        let encontradas: Vec<_> = repetidas
+            .iter()
+            .map(|r| match r.anterior {
+                Anterior::MesmaEntrega(prova) => (r.prova, r.exata, prova),
+                Anterior::Entrega(_) => panic!("nenhuma entrega registrada"),
+            })
+            .collect();
+        assert_eq!(encontradas, vec![(3, true, 1), (4, false, 1)]);
+    }
+
+    #[test]
+    fn earlier_deliveries_take_precedence_over_the_same_delivery() {
+        let original = png(&cena(1.25, 0));
+        let mut anterior = Entrega::from_provas("Liu Bei".to_string(), "liubei".to_string(), "shu".to_string(), &[]);
+        anterior.id = 7;
+        anterior.assinaturas.push(assinatura("a", &original));
+        let novas = [assinatura("x", &png(&cena(3.5, 0))), assinatura("a", &original), assinatura("a", &original)];
+        let repetidas = comparar(&[anterior], &novas);
+        assert_eq!(repetidas.len(), 2);
+        for repetida in &repetidas {
+            assert!(repetida.exata);
+            assert!(matches!(&repetida.anterior, Anterior::Entrega(entrega) if entrega.id == 7));
+        }
+    }
+}
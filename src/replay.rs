@@ -0,0 +1,327 @@
+// Gravação das atualizações recebidas e o subcomando replay, que as reprocessa contra um diretório
+// de rascunho e um Telegram de mentira, para reproduzir incidentes sem tocar nos dados reais
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{Method, Uri};
+use axum::Router;
+use frankenstein::updates::{Update, UpdateContent};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::{error, Instrument};
+
+use crate::bot::BotContext;
+use crate::config::Config;
+use crate::domain::{TimeDB, TIMES};
+use crate::error::{BotError, StorageError};
+use crate::handlers::{callback_span, process_callback, process_message, update_span};
+
+// Acrescenta cada atualização recebida, uma por linha, ao arquivo configurado em gravacao.arquivo
+pub struct UpdateRecorder {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl UpdateRecorder {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, BotError> {
+        let path = path.into();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|source| StorageError::Io { path: path.clone(), source })?;
+        Ok(UpdateRecorder { path, file: Mutex::new(file) })
+    }
+
+    pub fn record(&self, update: &Update) {
+        let line = match serde_json::to_string(update) {
+            Ok(line) => line,
+            Err(e) => {
+                error!(update_id = update.update_id, error = %e, "Falha ao serializar a atualização");
+                return;
+            }
+        };
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(e) = writeln!(file, "{}", line) {
+            error!(path = %self.path.display(), error = %e, "Falha ao gravar a atualização");
+        }
+    }
+}
+
+// Arquivos copiados do data_dir para o diretório de rascunho
+fn data_files() -> Vec<String> {
+    let mut files = vec!["inscritos.json".to_string(), "missoes.json".to_string()];
+    for time in TIMES {
+        files.push(format!("{}.json", time));
+        files.push(format!("registro_{}.json", time));
+    }
+    files
+}
+
+// Uma chamada de envio que o bot teria feito ao Telegram
+#[derive(Debug, Clone, Serialize)]
+pub struct SentMessage {
+    pub metodo: String,
+    pub chat_id: Option<i64>,
+    pub texto: Option<String>,
+    // Mensagem à qual o envio respondia, quando fazia parte de um fio, ou a mensagem editada
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resposta_a: Option<i32>,
+}
+
+// Telegram de mentira: responde com sucesso a qualquer método e anota o que o bot enviou. Como o replay
+// envia tudo na hora, uma atualização por vez, as anotações seguem sempre a mesma ordem
+#[derive(Default)]
+struct FakeTelegram {
+    sent: Mutex<Vec<SentMessage>>,
+    next_message_id: AtomicI32,
+    // Extensão de cada arquivo citado no log, para que o download devolva um conteúdo do tipo certo
+    extensoes: HashMap<String, &'static str>,
+}
+
+// Primeiros bytes de uma imagem JPEG e de um vídeo MP4, o bastante para passar pela checagem de formato
+const FAKE_JPEG: &[u8] = b"\xFF\xD8\xFF\xE0replay";
+const FAKE_MP4: &[u8] = b"\x00\x00\x00\x18ftypmp42replay";
+
+// Token qualquer, no formato esperado; nada sai da máquina durante o replay
+const REPLAY_TOKEN: &str = "000000:replay";
+
+// Reprocessa um log JSONL de atualizações e mostra o resultado. Se `scratch` não existir, ele é criado
+// com uma cópia dos arquivos JSON do data_dir; se já existir, é usado como está, o que permite partir
+// de um backup
+pub async fn replay(config: Config, log_path: &Path, scratch: Option<&Path>) -> Result<(), BotError> {
+    let output = replay_report(config, log_path, scratch).await?;
+    let data = serde_json::to_string_pretty(&output).map_err(|e| BotError::Parse(format!("resultado do replay: {}", e)))?;
+    println!("{}", data);
+    Ok(())
+}
+
+// O resultado do replay: o diretório usado, os bancos dos times ao final e as chamadas feitas ao Telegram
+pub async fn replay_report(config: Config, log_path: &Path, scratch: Option<&Path>) -> Result<Value, BotError> {
+    let log = fs::read_to_string(log_path).map_err(|source| StorageError::Io { path: log_path.to_path_buf(), source })?;
+    let scratch = prepare_scratch(&config.data_dir, scratch)?;
+
+    let mut updates = Vec::new();
+    for (i, line) in log.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let update: Update = serde_json::from_str(line)
+            .map_err(|e| BotError::Parse(format!("linha {} de {}: {}", i + 1, log_path.display(), e)))?;
+        updates.push(update);
+    }
+
+    let mut extensoes = HashMap::new();
+    for update in &updates {
+        if let UpdateContent::Message(message) = &update.content {
+            if let Some(video) = &message.video {
+                extensoes.insert(video.file_id.clone(), "mp4");
+            }
+            if let Some(video_note) = &message.video_note {
+                extensoes.insert(video_note.file_id.clone(), "mp4");
+            }
+        }
+    }
+    let fake = Arc::new(FakeTelegram { extensoes, ..FakeTelegram::default() });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|source| StorageError::Io { path: PathBuf::from("127.0.0.1:0"), source })?;
+    let address = listener
+        .local_addr()
+        .map_err(|source| StorageError::Io { path: PathBuf::from("127.0.0.1:0"), source })?;
+    let app = Router::new().fallback(fake_telegram).with_state(Arc::clone(&fake));
+    let server = tokio::spawn(async move {
+        let _ = axum::serve(listener, app).await;
+    });
+
+    let mut config = config;
+    config.data_dir = scratch.clone();
+    config.telegram.token = REPLAY_TOKEN.to_string();
+    config.telegram.api_url = format!("http://{}", address);
+    let ctx = BotContext::start(config);
+    ctx.outbox.send_immediately();
+    ctx.storage.inicializar_times();
+
+    let mut processed = 0;
+    for update in updates {
+        match update.content {
+            UpdateContent::Message(message) => {
+                let span = update_span(update.update_id, &message);
+                process_message(*message, &ctx).instrument(span).await;
+            }
+            UpdateContent::CallbackQuery(query) => {
+                let span = callback_span(update.update_id, &query);
+                process_callback(*query, &ctx).instrument(span).await;
+            }
+            _ => continue,
+        }
+        processed += 1;
+    }
+    ctx.outbox.flush().await;
+    server.abort();
+
+    let mut times = BTreeMap::new();
+    for time in TIMES {
+        let db: TimeDB = ctx.storage.read_time_db(time)?;
+        times.insert(time, db);
+    }
+    let sent = fake.sent.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    Ok(json!({
+        "diretorio": scratch,
+        "mensagens_processadas": processed,
+        "times": times,
+        "mensagens_enviadas": sent,
+    }))
+}
+
+fn prepare_scratch(data_dir: &Path, scratch: Option<&Path>) -> Result<PathBuf, BotError> {
+    let (scratch, existente) = match scratch {
+        Some(path) => (path.to_path_buf(), path.exists()),
+        None => (std::env::temp_dir().join(format!("yuanshao-replay-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f"))), false),
+    };
+    // O replay grava no diretório de trabalho; apontá-lo para os dados de verdade os sobrescreveria
+    if mesmo_diretorio(&scratch, data_dir) {
+        return Err(BotError::Config(format!(
+            "o diretório de trabalho do replay ({}) é o próprio DATA_DIR; escolha outro",
+            scratch.display()
+        )));
+    }
+    if existente {
+        return Ok(scratch);
+    }
+    fs::create_dir_all(&scratch).map_err(|source| StorageError::Io { path: scratch.clone(), source })?;
+    for file in data_files() {
+        let from = data_dir.join(&file);
+        if from.exists() {
+            let to = scratch.join(&file);
+            fs::copy(&from, &to).map_err(|source| StorageError::Io { path: to, source })?;
+        }
+    }
+    Ok(scratch)
+}
+
+// Compara os caminhos já resolvidos; um caminho que ainda não existe não pode ser o outro
+fn mesmo_diretorio(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+async fn fake_telegram(State(fake): State<Arc<FakeTelegram>>, method: Method, uri: Uri, body: Bytes) -> Vec<u8> {
+    // Download de arquivo: GET /file/bot<token>/<caminho>
+    if method == Method::GET && uri.path().starts_with("/file/") {
+        let conteudo = if uri.path().ends_with(".mp4") { FAKE_MP4 } else { FAKE_JPEG };
+        return conteudo.to_vec();
+    }
+
+    let api_method = uri.path().rsplit('/').next().unwrap_or("").to_string();
+    let fields = request_fields(&body);
+    let field = |name: &str| fields.get(name).cloned();
+    let chat_id = field("chat_id").and_then(|id| id.parse::<i64>().ok());
+
+    let result = match api_method.as_str() {
+        "getFile" => {
+            let file_id = field("file_id").unwrap_or_default();
+            let extensao = fake.extensoes.get(&file_id).copied().unwrap_or("jpg");
+            json!({ "file_id": file_id, "file_unique_id": file_id, "file_path": format!("replay/{}.{}", file_id, extensao) })
+        }
+        "sendMessage" | "sendPhoto" | "sendVideo" | "sendVideoNote" | "sendMediaGroup" => {
+            // Um álbum vira uma única anotação, com a legenda do primeiro item, mas uma mensagem por item
+            let media: Vec<Value> = field("media").and_then(|m| serde_json::from_str(&m).ok()).unwrap_or_default();
+            let texto = field("text").or_else(|| field("caption")).or_else(|| {
+                media.first().and_then(|item| item["caption"].as_str()).map(str::to_string)
+            });
+            let resposta_a = field("reply_parameters")
+                .and_then(|r| serde_json::from_str::<Value>(&r).ok())
+                .and_then(|r| r["message_id"].as_i64())
+                .map(|id| id as i32);
+            fake.sent
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(SentMessage { metodo: api_method.clone(), chat_id, texto, resposta_a });
+            let message = || {
+                json!({
+                    "message_id": fake.next_message_id.fetch_add(1, Ordering::Relaxed) + 1,
+                    "date": 0,
+                    "chat": { "id": chat_id.unwrap_or(0), "type": "private" },
+                })
+            };
+            if api_method == "sendMediaGroup" {
+                Value::Array(media.iter().map(|_| message()).collect())
+            } else {
+                message()
+            }
+        }
+        "editMessageText" | "editMessageCaption" | "answerCallbackQuery" => {
+            // A edição é anotada com a mensagem editada no lugar da resposta; a resposta a um botão, com o aviso mostrado
+            let texto = field("text").or_else(|| field("caption"));
+            let resposta_a = field("message_id").and_then(|id| id.parse().ok());
+            fake.sent
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(SentMessage { metodo: api_method.clone(), chat_id, texto, resposta_a });
+            json!(true)
+        }
+        "getChat" => {
+            // O mínimo que a resposta precisa ter para ser lida como um chat
+            let id = chat_id.unwrap_or(0);
+            let mut chat = json!({
+                "id": id,
+                "type": "private",
+                "first_name": "replay",
+                "accepted_gift_types": {
+                    "unlimited_gifts": false,
+                    "limited_gifts": false,
+                    "unique_gifts": false,
+                    "premium_subscription": false,
+                },
+            });
+            if id < 0 {
+                chat["type"] = json!("supergroup");
+                chat["title"] = json!("replay");
+            }
+            chat
+        }
+        _ => json!(true),
+    };
+    json!({ "ok": true, "result": result }).to_string().into_bytes()
+}
+
+// Campos de uma chamada à API, seja em JSON ou multipart (usado no envio de fotos)
+fn request_fields(body: &[u8]) -> BTreeMap<String, String> {
+    if let Ok(Value::Object(object)) = serde_json::from_slice::<Value>(body) {
+        return object
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                (key, value)
+            })
+            .collect();
+    }
+
+    // multipart: cada parte começa com um cabeçalho Content-Disposition com name="..."
+    let body = String::from_utf8_lossy(body);
+    let mut fields = BTreeMap::new();
+    for part in body.split("Content-Disposition: form-data; name=\"").skip(1) {
+        let Some((name, rest)) = part.split_once('"') else {
+            continue;
+        };
+        if rest.starts_with("; filename=") {
+            continue;
+        }
+        if let Some((_, value)) = rest.split_once("\r\n\r\n") {
+            let value = value.split("\r\n--").next().unwrap_or("");
+            fields.insert(name.to_string(), value.to_string());
+        }
+    }
+    fields
+}
@@ -0,0 +1,168 @@
+// Resumo de fim de partida do bot do jogo, encaminhado pelo jogador como prova: a lista de jogadores
+// com papel, se morreu e se venceu. Serve para uma pré-avaliação da missão, que o admin confirma
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Jogador {
+    // Nome como aparece no jogo, com os emojis do apelido
+    pub nome: String,
+    pub papel: String,
+    pub vivo: bool,
+    pub venceu: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumoPartida {
+    pub jogadores: Vec<Jogador>,
+}
+
+impl ResumoPartida {
+    pub fn vencedores(&self) -> impl Iterator<Item = &Jogador> {
+        self.jogadores.iter().filter(|j| j.venceu)
+    }
+
+    pub fn mortos(&self) -> impl Iterator<Item = &Jogador> {
+        self.jogadores.iter().filter(|j| !j.vivo)
+    }
+
+    // Procura o jogador pelo nome do Telegram, ignorando maiúsculas e o que o jogo acrescenta ao redor
+    pub fn jogador(&self, nome: &str) -> Option<&Jogador> {
+        let nome = nome.trim().to_lowercase();
+        if nome.is_empty() {
+            return None;
+        }
+        self.jogadores.iter().find(|j| j.nome.to_lowercase().contains(&nome))
+    }
+}
+
+// Marcas de jogador morto na lista final
+const MORTE: [&str; 2] = ["💀", "☠️"];
+
+// Cada jogador numa linha "Nome: [💀] Papel [-] Ganhou|Perdeu". Devolve None se o texto não tiver ao
+// menos dois jogadores, o que descarta mensagens comuns
+pub fn parse_resumo(texto: &str) -> Option<ResumoPartida> {
+    let linha_re = Regex::new(
+        r"(?i)^(.+?):\s*(.+?)\s*(?:-\s*)?\b(ganhou|venceu|vencedora?|won|perdeu|perdedora?|lost)\s*$",
+    )
+    .unwrap();
+
+    let jogadores: Vec<Jogador> = texto
+        .lines()
+        .filter_map(|linha| {
+            let caps = linha_re.captures(linha.trim())?;
+            let mut papel = caps[2].trim().to_string();
+            let mut vivo = true;
+            for marca in MORTE {
+                if papel.contains(marca) {
+                    vivo = false;
+                    papel = papel.replace(marca, "");
+                }
+            }
+            let resultado = caps[3].to_lowercase();
+            Some(Jogador {
+                nome: caps[1].trim().to_string(),
+                papel: papel.trim().trim_end_matches('-').trim().to_string(),
+                vivo,
+                venceu: ["ganhou", "venceu", "vencedor", "vencedora", "won"].contains(&resultado.as_str()),
+            })
+        })
+        .collect();
+
+    (jogadores.len() >= 2).then_some(ResumoPartida { jogadores })
+}
+
+// Resultado da pré-avaliação: o que confere e o que não confere com a missão
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Veredito {
+    pub confere: Vec<String>,
+    pub problemas: Vec<String>,
+}
+
+impl Veredito {
+    pub fn provavelmente_valida(&self) -> bool {
+        self.problemas.is_empty()
+    }
+}
+
+// Confere o resumo contra a missão (descrição como no texto das missões, sem o tipo) e contra a regra de
+// jogar com o emoji do time. `missao` e `emoji` ausentes pulam as respectivas checagens
+pub fn avaliar(resumo: &ResumoPartida, nome: &str, missao: Option<&str>, emoji: Option<&str>) -> Veredito {
+    let mut veredito = Veredito::default();
+    let Some(jogador) = resumo.jogador(nome) else {
+        veredito.problemas.push(format!("{} não aparece entre os jogadores", nome.trim()));
+        return veredito;
+    };
+    veredito.confere.push(format!("{} jogou como {}", jogador.nome, jogador.papel));
+
+    if let Some(emoji) = emoji {
+        if jogador.nome.contains(emoji) {
+            veredito.confere.push(format!("jogou com o emoji do time {}", emoji));
+        } else {
+            veredito.problemas.push(format!("jogou sem o emoji do time {}", emoji));
+        }
+    }
+
+    let Some(missao) = missao else {
+        return veredito;
+    };
+    let missao = sem_acentos(&missao.to_lowercase());
+
+    if let Some(papel) = papel_da_missao(&missao) {
+        if sem_acentos(&jogador.papel.to_lowercase()).contains(&papel) {
+            veredito.confere.push(format!("papel {} confere", jogador.papel));
+        } else {
+            veredito.problemas.push(format!("a missão pede {}, mas o papel foi {}", papel, jogador.papel));
+        }
+    }
+
+    let pede_vitoria = ["ganhe a partida", "ganhe o jogo", "venca a partida", "ganhe como"]
+        .iter()
+        .any(|trecho| missao.contains(trecho));
+    if pede_vitoria {
+        if jogador.venceu {
+            veredito.confere.push("venceu a partida".to_string());
+        } else {
+            veredito.problemas.push("a missão pede vitória, mas o jogador perdeu".to_string());
+        }
+    }
+
+    let pede_morte = ["morra", "seja enforcado", "seja devorado"].iter().any(|trecho| missao.contains(trecho));
+    let pede_sobreviver = missao.contains("sobreviva") && !pede_morte;
+    if pede_morte {
+        if jogador.vivo {
+            veredito.problemas.push("a missão pede a morte do jogador, que sobreviveu".to_string());
+        } else {
+            veredito.confere.push("morreu na partida".to_string());
+        }
+    } else if pede_sobreviver {
+        if jogador.vivo {
+            veredito.confere.push("sobreviveu".to_string());
+        } else {
+            veredito.problemas.push("a missão pede que o jogador sobreviva, mas morreu".to_string());
+        }
+    }
+    veredito
+}
+
+// O papel citado na missão: "Como ferreiro, ...", "Seja guarda", "Ganhe como golpista", "Seja um sacerdote"
+fn papel_da_missao(missao: &str) -> Option<String> {
+    let papel_re = Regex::new(r"\b(?:como|seja)\s+(?:um\s+|uma\s+)?(\p{L}+)").unwrap();
+    let papel = papel_re.captures(missao)?[1].to_string();
+    // "Como CH", "Como SK": siglas curtas demais para procurar no nome do papel
+    (papel.chars().count() >= 3).then_some(papel)
+}
+
+fn sem_acentos(texto: &str) -> String {
+    texto
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            other => other,
+        })
+        .collect()
+}
@@ -0,0 +1,254 @@
+// Fila de avaliação das entregas pendentes, com quem pegou cada uma, e as estatísticas das avaliações
+// por administrador. Só formata: os comandos que usam estes textos ficam em handlers
+use std::collections::HashMap;
+
+use crate::domain::{Entrega, StatusEntrega, TIMES};
+use crate::format::message_link;
+
+// Entregas pendentes por time, da mais antiga para a mais nova, seguidas dos recursos em aberto.
+// Entregas sem número, de antes da numeração, ficam de fora: não há como avaliá-las por comando
+pub fn format_fila(entregas: &[Entrega], admin_group_id: i64, agora: i64) -> String {
+    let mut pendentes: Vec<&Entrega> =
+        entregas.iter().filter(|e| e.id > 0 && e.status == StatusEntrega::Pendente).collect();
+    pendentes.sort_by_key(|e| (e.entregue_em, e.id));
+    let mut recursos: Vec<&Entrega> = entregas
+        .iter()
+        .filter(|e| e.recurso.as_ref().is_some_and(|recurso| recurso.decisao.is_none()))
+        .collect();
+    recursos.sort_by_key(|e| (e.recurso.as_ref().map_or(0, |recurso| recurso.aberto_em), e.id));
+
+    if pendentes.is_empty() && recursos.is_empty() {
+        return "📋 Nenhuma entrega aguardando avaliação. A fila está limpa!".to_string();
+    }
+
+    let mut text = format!("📋 Fila de avaliação: {} entrega(s) pendente(s)\n", pendentes.len());
+    for time in TIMES {
+        let do_time: Vec<&&Entrega> = pendentes.iter().filter(|e| e.time == time).collect();
+        if do_time.is_empty() {
+            continue;
+        }
+        text.push_str(&format!("\n{} ({})\n", time.to_uppercase(), do_time.len()));
+        for entrega in do_time {
+            let missao = entrega.missao.as_ref().map_or("missão não informada".to_string(), |missao| missao.to_string());
+            text.push_str(&format!("• Nº {} · {} · {} · {}", entrega.id, entrega.nome, missao, idade(entrega.entregue_em, agora)));
+            if let Some(reserva) = &entrega.responsavel {
+                text.push_str(&format!(" · 🙋 com {}", reserva.admin));
+            }
+            text.push('\n');
+            if let Some(link) = entrega.mensagens_admin.first().and_then(|id| message_link(admin_group_id, *id)) {
+                text.push_str(&format!("  {}\n", link));
+            }
+        }
+    }
+
+    if !recursos.is_empty() {
+        text.push_str(&format!("\n⚖️ Recursos em análise ({})\n", recursos.len()));
+        for entrega in recursos {
+            let Some(recurso) = &entrega.recurso else { continue };
+            text.push_str(&format!(
+                "• Nº {} · {} · {} · recusada por {}\n",
+                entrega.id,
+                entrega.nome,
+                idade(recurso.aberto_em, agora),
+                recurso.recusa.admin
+            ));
+            if let Some(link) = recurso.mensagens_admin.first().and_then(|id| message_link(admin_group_id, *id)) {
+                text.push_str(&format!("  {}\n", link));
+            }
+        }
+    }
+    text.push_str("\nUse /pegar Nº para avisar que está avaliando uma entrega e /soltar Nº para devolvê-la à fila.");
+    text
+}
+
+// Contagem das decisões de um administrador
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Placar {
+    pub admin: String,
+    pub aprovadas: u32,
+    pub recusadas: u32,
+    pub recursos_mantidos: u32,
+    pub recursos_revertidos: u32,
+    // Entregas pendentes que o administrador pegou e ainda não avaliou
+    pub em_analise: u32,
+    // Soma dos segundos entre a entrega e a avaliação, para a média
+    pub espera_total: i64,
+    pub esperas: u32,
+}
+
+impl Placar {
+    pub fn avaliadas(&self) -> u32 {
+        self.aprovadas + self.recusadas
+    }
+
+    pub fn espera_media(&self) -> Option<i64> {
+        (self.esperas > 0).then(|| self.espera_total / self.esperas as i64)
+    }
+}
+
+// Placar de cada administrador, do que mais avaliou para o que menos avaliou. Uma recusa revertida em
+// recurso conta como recusa de quem recusou e como reversão de quem decidiu o recurso
+pub fn placares(entregas: &[Entrega]) -> Vec<Placar> {
+    // Cada placar com o momento da ação de onde veio o nome do administrador
+    let mut placares: HashMap<i64, (i64, Placar)> = HashMap::new();
+
+    for entrega in entregas {
+        // A primeira avaliação: a recusa contestada, se houve recurso, ou a avaliação guardada
+        let primeira = match (&entrega.recurso, &entrega.avaliacao) {
+            (Some(recurso), _) => Some((&recurso.recusa, false)),
+            (None, Some(avaliacao)) => match entrega.status {
+                StatusEntrega::Aprovada => Some((avaliacao, true)),
+                StatusEntrega::Recusada => Some((avaliacao, false)),
+                _ => None,
+            },
+            (None, None) => None,
+        };
+        if let Some((avaliacao, aprovada)) = primeira {
+            let placar = placar(&mut placares, avaliacao.admin_id, &avaliacao.admin, avaliacao.em);
+            if aprovada {
+                placar.aprovadas += 1;
+            } else {
+                placar.recusadas += 1;
+            }
+            if entrega.entregue_em > 0 && avaliacao.em >= entrega.entregue_em {
+                placar.espera_total += avaliacao.em - entrega.entregue_em;
+                placar.esperas += 1;
+            }
+        }
+        if let Some(decisao) = entrega.recurso.as_ref().and_then(|recurso| recurso.decisao.as_ref()) {
+            let placar = placar(&mut placares, decisao.admin_id, &decisao.admin, decisao.em);
+            if decisao.revertida {
+                placar.recursos_revertidos += 1;
+            } else {
+                placar.recursos_mantidos += 1;
+            }
+        }
+        if let (StatusEntrega::Pendente, Some(reserva)) = (entrega.status, &entrega.responsavel) {
+            placar(&mut placares, reserva.admin_id, &reserva.admin, reserva.em).em_analise += 1;
+        }
+    }
+
+    let mut placares: Vec<Placar> = placares.into_values().map(|(_, placar)| placar).collect();
+    placares.sort_by(|a, b| b.avaliadas().cmp(&a.avaliadas()).then_with(|| a.admin.cmp(&b.admin)));
+    placares
+}
+
+// O placar de um administrador, com o nome da sua ação mais recente: as entregas não vêm na ordem das
+// avaliações, e o admin pode ter mudado de nome no Telegram entre uma e outra
+fn placar<'a>(placares: &'a mut HashMap<i64, (i64, Placar)>, admin_id: i64, admin: &str, em: i64) -> &'a mut Placar {
+    let (visto_em, placar) = placares.entry(admin_id).or_insert_with(|| (i64::MIN, Placar::default()));
+    if em >= *visto_em {
+        *visto_em = em;
+        placar.admin = admin.to_string();
+    }
+    placar
+}
+
+pub fn format_estatisticas(entregas: &[Entrega]) -> String {
+    let placares = placares(entregas);
+    if placares.is_empty() {
+        return "📈 Nenhuma entrega foi avaliada ainda.".to_string();
+    }
+    let pendentes = entregas.iter().filter(|e| e.id > 0 && e.status == StatusEntrega::Pendente).count();
+    let mut text = String::from("📈 Avaliações por administrador\n");
+    for placar in placares {
+        text.push_str(&format!(
+            "\n{}\n  ✅ {} aprovada(s) · ❌ {} recusada(s)\n",
+            placar.admin, placar.aprovadas, placar.recusadas
+        ));
+        if placar.recursos_mantidos + placar.recursos_revertidos > 0 {
+            text.push_str(&format!(
+                "  ⚖️ Recursos: {} mantido(s) · {} revertido(s)\n",
+                placar.recursos_mantidos, placar.recursos_revertidos
+            ));
+        }
+        if placar.em_analise > 0 {
+            text.push_str(&format!("  🙋 {} em análise\n", placar.em_analise));
+        }
+        if let Some(media) = placar.espera_media() {
+            text.push_str(&format!("  ⏱️ Resposta em {} em média\n", duracao(media)));
+        }
+    }
+    text.push_str(&format!("\nAinda na fila: {} entrega(s). Veja-as com /fila.", pendentes));
+    text
+}
+
+// "há 5 min", "há 3 h"; desde 0 é uma entrega de antes do registro da hora
+fn idade(desde: i64, agora: i64) -> String {
+    if desde <= 0 {
+        return "sem data".to_string();
+    }
+    format!("há {}", duracao(agora - desde))
+}
+
+fn duracao(segundos: i64) -> String {
+    match segundos.max(0) {
+        s if s < 60 => "menos de 1 min".to_string(),
+        s if s < 3600 => format!("{} min", s / 60),
+        s if s < 86400 => format!("{} h", s / 3600),
+        s => format!("{} d", s / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Avaliacao, DecisaoRecurso, Recurso, Reserva};
+
+    fn avaliacao(admin_id: i64, em: i64) -> Avaliacao {
+        Avaliacao { admin: format!("Admin {}", admin_id), admin_id, motivo: None, em }
+    }
+
+    fn entrega(status: StatusEntrega, entregue_em: i64) -> Entrega {
+        let mut entrega = Entrega::from_provas("Zhao".to_string(), "zhao".to_string(), "wei".to_string(), &[]);
+        entrega.status = status;
+        entrega.entregue_em = entregue_em;
+        entrega
+    }
+
+    #[test]
+    fn scores_count_the_appealed_rejection_and_pending_claims() {
+        let mut aprovada = entrega(StatusEntrega::Aprovada, 100);
+        aprovada.avaliacao = Some(avaliacao(1, 400));
+
+        // Recusada pelo admin 2 e aprovada em recurso pelo admin 1, que passa a constar da avaliação
+        let mut revertida = entrega(StatusEntrega::Aprovada, 100);
+        revertida.avaliacao = Some(avaliacao(1, 900));
+        revertida.recurso = Some(Recurso {
+            argumento: "o print estava no álbum".to_string(),
+            textos: Vec::new(),
+            arquivos: Vec::new(),
+            mensagens_admin: Vec::new(),
+            recusa: avaliacao(2, 700),
+            aberto_em: 800,
+            decisao: Some(DecisaoRecurso { revertida: true, admin: "Admin 1".to_string(), admin_id: 1, em: 900 }),
+        });
+
+        let mut reservada = entrega(StatusEntrega::Pendente, 100);
+        reservada.responsavel = Some(Reserva { admin: "Admin 2".to_string(), admin_id: 2, em: 200 });
+
+        let placares = placares(&[aprovada, revertida, reservada]);
+        assert_eq!(placares.len(), 2);
+        let (primeiro, segundo) = (&placares[0], &placares[1]);
+        assert_eq!((primeiro.admin.as_str(), primeiro.aprovadas, primeiro.recusadas), ("Admin 1", 1, 0));
+        assert_eq!((primeiro.recursos_revertidos, primeiro.recursos_mantidos), (1, 0));
+        assert_eq!(primeiro.espera_media(), Some(300));
+        assert_eq!((segundo.admin.as_str(), segundo.aprovadas, segundo.recusadas), ("Admin 2", 0, 1));
+        assert_eq!(segundo.em_analise, 1);
+        assert_eq!(segundo.espera_media(), Some(600));
+    }
+
+    #[test]
+    fn scores_show_the_name_of_the_latest_action() {
+        let renomeado = |nome: &str, em: i64| Avaliacao { admin: nome.to_string(), ..avaliacao(1, em) };
+        let mut recente = entrega(StatusEntrega::Aprovada, 100);
+        recente.avaliacao = Some(renomeado("Cao Cao", 900));
+        let mut antiga = entrega(StatusEntrega::Recusada, 100);
+        antiga.avaliacao = Some(renomeado("Mengde", 300));
+
+        // A entrega avaliada por último vem primeiro na lista
+        let placares = placares(&[recente, antiga]);
+        assert_eq!(placares.len(), 1);
+        assert_eq!((placares[0].admin.as_str(), placares[0].avaliadas()), ("Cao Cao", 2));
+    }
+}
@@ -0,0 +1,481 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tracing::{error, info, warn};
+
+use crate::conversa::{Rascunho, Sessao};
+use crate::domain::{Entrega, Inscrito, Missao, MissaoCumprida, Naipe, StatusEntrega, TimeDB, TIMES};
+use crate::error::{BotError, StorageError};
+
+// Arquivos de dados do bot, todos relativos a um diretório raiz
+pub struct Storage {
+    root: PathBuf,
+    // Serializa as operações de leitura-modificação-escrita para que entregas simultâneas não se percam
+    write_lock: std::sync::Mutex<()>,
+}
+
+impl Storage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Storage {
+            root: root.into(),
+            write_lock: std::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self, relative: &str) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.write_lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn read_json<T: DeserializeOwned>(&self, relative: &str) -> Result<T, BotError> {
+        let path = self.path(relative);
+        let data = fs::read_to_string(&path).map_err(|source| StorageError::Io { path: path.clone(), source })?;
+        Ok(serde_json::from_str(&data).map_err(|source| StorageError::Json { path, source })?)
+    }
+
+    pub(crate) fn write_json<T: Serialize + ?Sized>(&self, relative: &str, value: &T) -> Result<(), BotError> {
+        let path = self.path(relative);
+        let data = serde_json::to_string_pretty(value).map_err(|source| StorageError::Json { path: path.clone(), source })?;
+        // Grava ao lado e renomeia, para que uma leitura sem a trava nunca encontre o arquivo pela metade
+        let partial = path.with_extension("json.tmp");
+        fs::write(&partial, data)
+            .and_then(|()| fs::rename(&partial, &path))
+            .map_err(|source| StorageError::Io { path, source })?;
+        Ok(())
+    }
+
+    pub fn read_inscritos(&self) -> Result<Vec<Inscrito>, BotError> {
+        if !self.path("inscritos.json").exists() {
+            return Ok(Vec::new());
+        }
+        self.read_json("inscritos.json")
+    }
+
+    pub fn write_inscritos(&self, inscritos: &[Inscrito]) -> Result<(), BotError> {
+        self.write_json("inscritos.json", inscritos)
+    }
+
+    // Adiciona um inscrito com o próximo número de inscrição
+    pub fn add_inscrito(&self, nome: String, user: String) -> Result<Inscrito, BotError> {
+        let _guard = self.lock();
+        let mut inscritos = self.read_inscritos()?;
+        let new_inscrito = Inscrito {
+            id: inscritos.len() as u32 + 1,
+            nome,
+            user,
+            user_id: None,
+            time: None,
+        };
+        inscritos.push(new_inscrito.clone());
+        self.write_inscritos(&inscritos)?;
+        Ok(new_inscrito)
+    }
+
+    // Acrescenta vários inscritos de uma vez, ignorando usuários já inscritos.
+    // Devolve os que foram adicionados e quantos foram ignorados
+    pub fn import_inscritos(&self, novos: Vec<(String, String)>) -> Result<(Vec<Inscrito>, usize), BotError> {
+        let _guard = self.lock();
+        let mut inscritos = self.read_inscritos()?;
+        let mut added = Vec::new();
+        let mut skipped = 0;
+        for (nome, user) in novos {
+            let user = user.trim().trim_start_matches('@').to_string();
+            if inscritos.iter().any(|i| i.user.eq_ignore_ascii_case(&user)) {
+                skipped += 1;
+                continue;
+            }
+            let inscrito = Inscrito {
+                id: inscritos.len() as u32 + 1,
+                nome: nome.trim().to_string(),
+                user,
+                user_id: None,
+                time: None,
+            };
+            inscritos.push(inscrito.clone());
+            added.push(inscrito);
+        }
+        self.write_inscritos(&inscritos)?;
+        Ok((added, skipped))
+    }
+
+    // Liga o inscrito de mesmo usuário ao seu ID do Telegram e ao time em que ele entrega missões.
+    // O primeiro time informado fica, para que uma resposta errada numa entrega não mude o time do
+    // jogador; quem corrige é um admin, com /mudartime (corrigir_time). Devolve false se o usuário não
+    // estiver inscrito
+    pub fn vincular_inscrito(&self, user: &str, user_id: i64, time: &str) -> Result<bool, BotError> {
+        let _guard = self.lock();
+        let mut inscritos = self.read_inscritos()?;
+        let Some(inscrito) = inscritos.iter_mut().find(|i| !user.is_empty() && i.user.eq_ignore_ascii_case(user)) else {
+            return Ok(false);
+        };
+        if inscrito.user_id == Some(user_id) && inscrito.time.is_some() {
+            return Ok(true);
+        }
+        inscrito.user_id = Some(user_id);
+        inscrito.time.get_or_insert_with(|| time.to_string());
+        self.write_inscritos(&inscritos)?;
+        Ok(true)
+    }
+
+    // Troca o time do inscrito, por decisão de um admin. As entregas já feitas continuam no time em que
+    // foram registradas. Devolve o inscrito com o time anterior, ou None se o usuário não estiver inscrito
+    pub fn corrigir_time(&self, user: &str, time: &str) -> Result<Option<(Inscrito, Option<String>)>, BotError> {
+        let _guard = self.lock();
+        let mut inscritos = self.read_inscritos()?;
+        let user = user.trim_start_matches('@');
+        let Some(inscrito) = inscritos.iter_mut().find(|i| !user.is_empty() && i.user.eq_ignore_ascii_case(user)) else {
+            return Ok(None);
+        };
+        let anterior = inscrito.time.replace(time.to_string());
+        let inscrito = inscrito.clone();
+        self.write_inscritos(&inscritos)?;
+        Ok(Some((inscrito, anterior)))
+    }
+
+    pub fn read_missoes(&self) -> Result<Vec<Missao>, BotError> {
+        if !self.path("missoes.json").exists() {
+            self.write_json::<[Missao]>("missoes.json", &[])?;
+        }
+        self.read_json("missoes.json")
+    }
+
+    pub fn read_entregas(&self, time: &str) -> Result<Vec<Entrega>, BotError> {
+        let file_path = format!("registro_{}.json", time);
+        if !self.path(&file_path).exists() {
+            return Ok(Vec::new());
+        }
+        self.read_json(&file_path)
+    }
+
+    pub fn write_entregas(&self, time: &str, entregas: &[Entrega]) -> Result<(), BotError> {
+        self.write_json(&format!("registro_{}.json", time), entregas)
+    }
+
+    // Registra a entrega com o próximo número livre entre os três times e devolve esse número. Partidas
+    // já usadas pelo time para a mesma missão são conferidas sob a trava, para que dois colegas entregando
+    // ao mesmo tempo não reivindiquem a mesma partida
+    pub fn append_entrega(&self, mut entrega: Entrega) -> Result<u32, BotError> {
+        let _guard = self.lock();
+        self.conferir_partidas(&entrega.time, entrega.missao.as_ref(), &entrega.partidas, None)?;
+        let mut ultimo = 0;
+        for time in TIMES {
+            ultimo = self.read_entregas(time)?.iter().map(|e| e.id).fold(ultimo, u32::max);
+        }
+        entrega.id = ultimo + 1;
+        let mut entregas = self.read_entregas(&entrega.time)?;
+        let time = entrega.time.clone();
+        entregas.push(entrega);
+        self.write_entregas(&time, &entregas)?;
+        Ok(ultimo + 1)
+    }
+
+    pub fn buscar_entrega(&self, id: u32) -> Result<Option<Entrega>, BotError> {
+        for time in TIMES {
+            if let Some(entrega) = self.read_entregas(time)?.into_iter().find(|e| e.id == id) {
+                return Ok(Some(entrega));
+            }
+        }
+        Ok(None)
+    }
+
+    // Lê, altera e grava uma entrega pelo número. Devolve None se não houver entrega com esse número
+    pub fn update_entrega<F>(&self, id: u32, update: F) -> Result<Option<Entrega>, BotError>
+    where
+        F: FnOnce(&mut Entrega) -> Result<(), BotError>,
+    {
+        if id == 0 {
+            return Ok(None);
+        }
+        let _guard = self.lock();
+        for time in TIMES {
+            let mut entregas = self.read_entregas(time)?;
+            if let Some(entrega) = entregas.iter_mut().find(|e| e.id == id) {
+                update(entrega)?;
+                let entrega = entrega.clone();
+                self.write_entregas(time, &entregas)?;
+                return Ok(Some(entrega));
+            }
+        }
+        Ok(None)
+    }
+
+    // Altera uma entrega que passa a aprovada e credita a missão dela ao time, sob a mesma trava: se o
+    // crédito falhar, a entrega não é gravada e pode ser avaliada de novo
+    pub fn aprovar_entrega<F>(&self, id: u32, update: F) -> Result<Option<Entrega>, BotError>
+    where
+        F: FnOnce(&mut Entrega) -> Result<(), BotError>,
+    {
+        if id == 0 {
+            return Ok(None);
+        }
+        let _guard = self.lock();
+        for time in TIMES {
+            let mut entregas = self.read_entregas(time)?;
+            let Some(entrega) = entregas.iter_mut().find(|e| e.id == id) else {
+                continue;
+            };
+            update(entrega)?;
+            let entrega = entrega.clone();
+            let missao = entrega.missao.as_ref()
+                .ok_or_else(|| BotError::Recusa(format!("A entrega Nº {} não informa a missão.", id)))?;
+            let anterior = self.read_time_db(time)?;
+            let mut db = anterior.clone();
+            creditar(&mut db, time, missao.naipe, &missao.tipo, 1)?;
+            self.write_time_db(time, &db)?;
+            if let Err(e) = self.write_entregas(time, &entregas) {
+                // Sem a entrega gravada como aprovada, o crédito também não pode ficar
+                if let Err(desfazer) = self.write_time_db(time, &anterior) {
+                    error!(time, entrega = id, error = %desfazer, "Falha ao desfazer o crédito de uma entrega não gravada");
+                }
+                return Err(e);
+            }
+            return Ok(Some(entrega));
+        }
+        Ok(None)
+    }
+
+    // Entrega à qual pertence uma mensagem do grupo de administração
+    pub fn entrega_da_mensagem_admin(&self, message_id: i32) -> Result<Option<Entrega>, BotError> {
+        for time in TIMES {
+            if let Some(entrega) = self.read_entregas(time)?.into_iter().find(|e| e.mensagens_admin.contains(&message_id)) {
+                return Ok(Some(entrega));
+            }
+        }
+        Ok(None)
+    }
+
+    // Todas as entregas dos três times
+    pub fn todas_entregas(&self) -> Result<Vec<Entrega>, BotError> {
+        let mut entregas = Vec::new();
+        for time in TIMES {
+            entregas.extend(self.read_entregas(time)?);
+        }
+        Ok(entregas)
+    }
+
+    // Entregas de um jogador nos três times, da mais antiga para a mais recente
+    pub fn entregas_do_jogador(&self, user_id: i64) -> Result<Vec<Entrega>, BotError> {
+        let mut entregas: Vec<Entrega> = self.todas_entregas()?.into_iter().filter(|e| e.user_id == Some(user_id)).collect();
+        entregas.sort_by_key(|e| (e.entregue_em, e.id));
+        Ok(entregas)
+    }
+
+    // Arquivos guardados de todas as entregas de um jogador, inclusive as retiradas
+    pub fn arquivos_do_jogador(&self, user_id: i64) -> Result<usize, BotError> {
+        Ok(self.entregas_do_jogador(user_id)?.iter().map(Entrega::arquivos).sum())
+    }
+
+    // Uma partida vale uma só vez por missão dentro de cada time: recusa, com a resposta ao jogador, as
+    // partidas que outra entrega do time já usou para a mesma missão. Entregas retiradas não contam, nem a
+    // entrega `exceto`, que está recebendo novas provas
+    pub fn conferir_partidas(
+        &self,
+        time: &str,
+        missao: Option<&MissaoCumprida>,
+        partidas: &[String],
+        exceto: Option<u32>,
+    ) -> Result<(), BotError> {
+        let Some(missao) = missao else {
+            return Ok(());
+        };
+        if partidas.is_empty() {
+            return Ok(());
+        }
+        let entregas = self.read_entregas(time)?;
+        let validas = entregas
+            .into_iter()
+            .filter(|e| e.status != StatusEntrega::Retirada && Some(e.id) != exceto);
+        let reivindicada = validas.filter(|e| e.missao.as_ref() == Some(missao)).find_map(|entrega| {
+            let partida = entrega.partidas.iter().find(|p| partidas.contains(p))?.clone();
+            Some((entrega, partida))
+        });
+        match reivindicada {
+            Some((anterior, partida)) => Err(BotError::Recusa(format!(
+                "A partida {} já foi apresentada por {} para a missão do {}. Cada partida vale uma só vez por missão em um mesmo time; envie outra partida ou use /cancelar.",
+                partida, anterior.nome, missao
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn read_time_db(&self, time: &str) -> Result<TimeDB, BotError> {
+        self.read_json(&format!("{}.json", time))
+    }
+
+    pub fn write_time_db(&self, time: &str, db: &TimeDB) -> Result<(), BotError> {
+        self.write_json(&format!("{}.json", time), db)
+    }
+
+    // Lê, altera e grava o banco de um time sem que outra atualização se intercale
+    pub fn update_time_db<F>(&self, time: &str, update: F) -> Result<TimeDB, BotError>
+    where
+        F: FnOnce(&mut TimeDB) -> Result<(), BotError>,
+    {
+        let _guard = self.lock();
+        let mut db = self.read_time_db(time)?;
+        update(&mut db)?;
+        self.write_time_db(time, &db)?;
+        Ok(db)
+    }
+
+    // Soma `delta` às missões de um tipo num naipe do time, sem deixar a contagem negativa
+    pub fn creditar_missao(&self, time: &str, naipe_idx: usize, missao: &str, delta: i32) -> Result<TimeDB, BotError> {
+        self.update_time_db(time, |db| creditar(db, time, naipe_idx, missao, delta))
+    }
+
+    pub fn set_soldados(&self, time: &str, soldados: i32) -> Result<TimeDB, BotError> {
+        self.update_time_db(time, |db| {
+            db.soldados = soldados;
+            Ok(())
+        })
+    }
+
+    // Apaga as provas e o registro de entregas de um time. Devolve quantos arquivos foram removidos
+    pub fn reset_entregas(&self, time: &str) -> Result<usize, BotError> {
+        let _guard = self.lock();
+        let dir = self.path(&self.entregas_dir(time));
+        let mut removed = 0;
+        if let Ok(files) = fs::read_dir(&dir) {
+            for file in files.flatten() {
+                let path = file.path();
+                if path.is_file() {
+                    fs::remove_file(&path).map_err(|source| StorageError::Io { path, source })?;
+                    removed += 1;
+                }
+            }
+        }
+        self.write_entregas(time, &[])?;
+        Ok(removed)
+    }
+
+    // Conversas gravadas no último encerramento. O arquivo é removido após a leitura,
+    // para que uma queda posterior não ressuscite conversas já encerradas
+    pub fn take_saved_states(&self) -> Result<HashMap<i64, Sessao>, BotError> {
+        if !self.path("estados.json").exists() {
+            return Ok(HashMap::new());
+        }
+        let states = self.read_json("estados.json")?;
+        let path = self.path("estados.json");
+        fs::remove_file(&path).map_err(|source| StorageError::Io { path, source })?;
+        Ok(states)
+    }
+
+    pub fn save_states(&self, states: &HashMap<i64, Sessao>) -> Result<(), BotError> {
+        if states.is_empty() {
+            return Ok(());
+        }
+        self.write_json("estados.json", states)
+    }
+
+    fn read_rascunhos(&self) -> Result<HashMap<i64, Rascunho>, BotError> {
+        if !self.path("rascunhos.json").exists() {
+            return Ok(HashMap::new());
+        }
+        self.read_json("rascunhos.json")
+    }
+
+    // Guarda as provas de uma conversa expirada; um rascunho anterior do mesmo usuário é substituído e devolvido
+    pub fn save_rascunho(&self, user_id: i64, rascunho: Rascunho) -> Result<Option<Rascunho>, BotError> {
+        let _guard = self.lock();
+        let mut rascunhos = self.read_rascunhos()?;
+        let previous = rascunhos.insert(user_id, rascunho);
+        self.write_json("rascunhos.json", &rascunhos)?;
+        Ok(previous)
+    }
+
+    pub fn take_rascunho(&self, user_id: i64) -> Result<Option<Rascunho>, BotError> {
+        let _guard = self.lock();
+        let mut rascunhos = self.read_rascunhos()?;
+        let rascunho = rascunhos.remove(&user_id);
+        if rascunho.is_some() {
+            self.write_json("rascunhos.json", &rascunhos)?;
+        }
+        Ok(rascunho)
+    }
+
+    // Retira os rascunhos salvos antes de `limite` (segundos Unix) e os devolve, para que as provas sejam apagadas
+    pub fn take_expired_rascunhos(&self, limite: i64) -> Result<Vec<Rascunho>, BotError> {
+        let _guard = self.lock();
+        let mut rascunhos = self.read_rascunhos()?;
+        let expired: Vec<i64> = rascunhos.iter().filter(|(_, r)| r.salvo_em < limite).map(|(id, _)| *id).collect();
+        if expired.is_empty() {
+            return Ok(Vec::new());
+        }
+        let removed = expired.iter().filter_map(|id| rascunhos.remove(id)).collect();
+        self.write_json("rascunhos.json", &rascunhos)?;
+        Ok(removed)
+    }
+
+    // Apaga todos os rascunhos, devolvendo quantos havia
+    pub fn clear_rascunhos(&self) -> Result<usize, BotError> {
+        let _guard = self.lock();
+        let count = self.read_rascunhos()?.len();
+        if count > 0 {
+            self.write_json::<HashMap<i64, Rascunho>>("rascunhos.json", &HashMap::new())?;
+        }
+        Ok(count)
+    }
+
+    // Remove downloads interrompidos (arquivos .part) deixados por uma execução anterior
+    pub fn remove_partial_downloads(&self) {
+        let Ok(times) = fs::read_dir(self.path("entregas")) else {
+            return;
+        };
+        for dir in times.flatten() {
+            let Ok(files) = fs::read_dir(dir.path()) else {
+                continue;
+            };
+            for file in files.flatten() {
+                let path = file.path();
+                if path.extension().is_some_and(|ext| ext == "part") {
+                    match fs::remove_file(&path) {
+                        Ok(()) => info!(path = %path.display(), "Download interrompido removido"),
+                        Err(e) => warn!(path = %path.display(), error = %e, "Falha ao remover download interrompido"),
+                    }
+                }
+            }
+        }
+    }
+
+    // Diretório das provas de um time, relativo à raiz (ex.: "entregas/shu")
+    pub fn entregas_dir(&self, time: &str) -> String {
+        format!("entregas/{}", time)
+    }
+
+    pub fn inicializar_times(&self) {
+        for time in &TIMES {
+            let file_path = self.path(&format!("{}.json", time));
+            if !file_path.exists() {
+                let naipes = vec![Naipe::default(); 22];
+                let db = TimeDB {
+                    soldados: 10000,
+                    naipes,
+                };
+                let data = serde_json::to_string_pretty(&db).unwrap();
+                if let Err(e) = fs::write(&file_path, data) {
+                    error!(time, error = %e, "Falha ao criar o banco de dados do time");
+                } else {
+                    info!(time, "Banco de dados do time criado com sucesso.");
+                }
+            }
+        }
+    }
+}
+
+// Crédito de missão no banco já lido de um time; quem chama grava
+fn creditar(db: &mut TimeDB, time: &str, naipe_idx: usize, missao: &str, delta: i32) -> Result<(), BotError> {
+    let naipe = db.naipes.get_mut(naipe_idx.wrapping_sub(1))
+        .ok_or_else(|| BotError::Parse(format!("naipe {} ausente no banco do time {}", naipe_idx, time)))?;
+    match missao {
+        "pedra" => naipe.pedra = (naipe.pedra as i32 + delta).max(0) as u32,
+        "papel" => naipe.papel = (naipe.papel as i32 + delta).max(0) as u32,
+        "tesoura" => naipe.tesoura = (naipe.tesoura as i32 + delta).max(0) as u32,
+        _ => {}
+    }
+    Ok(())
+}
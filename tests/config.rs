@@ -0,0 +1,226 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use yuanshao_bot::config::DEFAULT_TELEGRAM_API_URL;
+use yuanshao_bot::{check_config, Config};
+
+fn com_api_url(api_url: &str) -> Config {
+    let mut config = Config::default();
+    config.telegram.token = "123456:segredo".to_string();
+    config.telegram.api_url = api_url.to_string();
+    config
+}
+
+#[test]
+fn api_urls_for_the_default_and_a_custom_base() {
+    let config = com_api_url(DEFAULT_TELEGRAM_API_URL);
+    assert_eq!(config.bot_api_url(), "https://api.telegram.org/bot123456:segredo");
+    assert_eq!(
+        config.file_download_url("photos/file_1.jpg"),
+        "https://api.telegram.org/file/bot123456:segredo/photos/file_1.jpg"
+    );
+
+    for api_url in ["http://127.0.0.1:8081", "http://127.0.0.1:8081/"] {
+        let config = com_api_url(api_url);
+        assert_eq!(config.bot_api_url(), "http://127.0.0.1:8081/bot123456:segredo");
+        assert_eq!(
+            config.file_download_url("videos/file_2.mp4"),
+            "http://127.0.0.1:8081/file/bot123456:segredo/videos/file_2.mp4"
+        );
+    }
+}
+
+// As variáveis de ambiente valem para o processo inteiro; os testes que mexem nelas passam um de cada vez
+static ENV: Mutex<()> = Mutex::new(());
+
+const VARIAVEIS: &[&str] = &[
+    "YUANSHAO_CONFIG",
+    "TELEGRAM_BOT_TOKEN",
+    "TELEGRAM_API_URL",
+    "DATA_DIR",
+    "METRICS_ADDR",
+    "UPDATES_LOG",
+    "LOG_FORMAT",
+    "SESSION_TIMEOUT_MINUTES",
+    "SESSION_REMINDER_MINUTES",
+    "DRAFT_RETENTION_DAYS",
+    "MAX_FILE_MB",
+    "MAX_FILES_PER_DELIVERY",
+    "MAX_FILES_PER_PLAYER",
+    "SHU_EMOJI",
+    "WEI_EMOJI",
+    "WU_EMOJI",
+    "ADMIN_GROUP_ID",
+    "SHU_GROUP_ID",
+    "WEI_GROUP_ID",
+    "WU_GROUP_ID",
+];
+
+// Um arquivo completo e válido; cada teste estraga uma parte dele pelo ambiente ou pelo próprio TOML
+fn toml_valido(data_dir: &Path) -> String {
+    format!(
+        r#"data_dir = {:?}
+
+[telegram]
+token = "123456:segredo"
+
+[grupos]
+admin = -100
+shu = -1
+wei = -2
+wu = -3
+"#,
+        data_dir
+    )
+}
+
+// Carrega o TOML com exatamente estas variáveis de ambiente definidas
+fn carregar(toml: &str, env: &[(&str, &str)]) -> Result<Config, String> {
+    let _guard = ENV.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("yuanshao.toml");
+    fs::write(&path, toml).unwrap();
+    for var in VARIAVEIS {
+        std::env::remove_var(var);
+    }
+    for (var, value) in env {
+        std::env::set_var(var, value);
+    }
+    let result = Config::load(Some(&path)).map(|(config, _)| config).map_err(|e| e.to_string());
+    for (var, _) in env {
+        std::env::remove_var(var);
+    }
+    result
+}
+
+#[test]
+fn a_complete_file_loads() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let config = carregar(&toml_valido(data_dir.path()), &[]).unwrap();
+    assert_eq!(config.telegram.token, "123456:segredo");
+    assert_eq!(config.grupos.admin, -100);
+    assert_eq!(config.limites.tamanho_maximo_mb, 20);
+}
+
+#[test]
+fn a_missing_token_is_reported() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let toml = toml_valido(data_dir.path()).replace("token = \"123456:segredo\"", "");
+    let erro = carregar(&toml, &[]).unwrap_err();
+    assert!(erro.contains("token do bot ausente"), "{}", erro);
+}
+
+#[test]
+fn a_bad_admin_group_id_is_reported() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let erro = carregar(&toml_valido(data_dir.path()), &[("ADMIN_GROUP_ID", "grupo-dos-admins")]).unwrap_err();
+    assert!(erro.contains("ADMIN_GROUP_ID não é um ID de chat válido"), "{}", erro);
+
+    let toml = toml_valido(data_dir.path()).replace("admin = -100", "");
+    let erro = carregar(&toml, &[]).unwrap_err();
+    assert!(erro.contains("grupo admin não configurado"), "{}", erro);
+
+    let toml = toml_valido(data_dir.path()).replace("admin = -100", "admin = -1");
+    let erro = carregar(&toml, &[]).unwrap_err();
+    assert!(erro.contains("mesmo ID"), "{}", erro);
+}
+
+#[test]
+fn zero_and_invalid_numeric_overrides_are_reported() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let toml = toml_valido(data_dir.path());
+
+    let erro = carregar(&toml, &[("MAX_FILE_MB", "0")]).unwrap_err();
+    assert!(erro.contains("devem ser maiores que zero"), "{}", erro);
+    let erro = carregar(&toml, &[("MAX_FILES_PER_DELIVERY", "0")]).unwrap_err();
+    assert!(erro.contains("devem ser maiores que zero"), "{}", erro);
+
+    let erro = carregar(&toml, &[("SESSION_TIMEOUT_MINUTES", "trinta"), ("MAX_FILES_PER_PLAYER", "-5")]).unwrap_err();
+    assert!(erro.contains("SESSION_TIMEOUT_MINUTES deve ser um número inteiro"), "{}", erro);
+    assert!(erro.contains("MAX_FILES_PER_PLAYER deve ser um número inteiro"), "{}", erro);
+
+    // Grandes demais: as contas em bytes e em segundos estourariam
+    let erro = carregar(&toml, &[("MAX_FILE_MB", "18446744073709551615"), ("DRAFT_RETENTION_DAYS", "400000000000000")]).unwrap_err();
+    assert!(erro.contains("limites.tamanho_maximo_mb deve ser no máximo 2000"), "{}", erro);
+    assert!(erro.contains("conversas.rascunho_dias deve ser no máximo 365"), "{}", erro);
+    let erro = carregar(&toml, &[("SESSION_TIMEOUT_MINUTES", "99999999")]).unwrap_err();
+    assert!(erro.contains("conversas.expiracao_minutos deve ser no máximo"), "{}", erro);
+
+    let erro = carregar(&toml, &[("SESSION_TIMEOUT_MINUTES", "10"), ("SESSION_REMINDER_MINUTES", "10")]).unwrap_err();
+    assert!(erro.contains("lembrete_minutos (10) deve ser menor"), "{}", erro);
+}
+
+#[test]
+fn a_missing_data_dir_is_reported() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let ausente = data_dir.path().join("nao-existe");
+    let erro = carregar(&toml_valido(&ausente), &[]).unwrap_err();
+    assert!(erro.contains("data_dir não é um diretório"), "{}", erro);
+}
+
+#[test]
+fn environment_variables_take_precedence_over_the_file() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let outro_dir = tempfile::tempdir().unwrap();
+    let config = carregar(
+        &toml_valido(data_dir.path()),
+        &[
+            ("TELEGRAM_BOT_TOKEN", " 654321:outro "),
+            ("ADMIN_GROUP_ID", "-200"),
+            ("DATA_DIR", outro_dir.path().to_str().unwrap()),
+            ("MAX_FILE_MB", "5"),
+        ],
+    )
+    .unwrap();
+    assert_eq!(config.telegram.token, "654321:outro");
+    assert_eq!(config.grupos.admin, -200);
+    assert_eq!(config.data_dir, outro_dir.path());
+    assert_eq!(config.limites.tamanho_maximo_mb, 5);
+    // O que o ambiente não define continua vindo do arquivo
+    assert_eq!(config.grupos.shu, -1);
+}
+
+#[tokio::test]
+async fn check_config_reports_unreachable_groups() {
+    let mut server = mockito::Server::new_async().await;
+    let _me = server
+        .mock("POST", "/bot123456:segredo/getMe")
+        .with_body(r#"{"ok":true,"result":{"id":1,"is_bot":true,"first_name":"Yuan Shao","username":"yuanshao_bot"}}"#)
+        .create_async()
+        .await;
+    let chat = |id: i64| {
+        format!(
+            r#"{{"ok":true,"result":{{"id":{},"type":"supergroup","title":"Grupo","accepted_gift_types":{{"unlimited_gifts":false,"limited_gifts":false,"unique_gifts":false,"premium_subscription":false}}}}}}"#,
+            id
+        )
+    };
+    let _chats = server
+        .mock("POST", "/bot123456:segredo/getChat")
+        .with_body_from_request(move |request| {
+            let body = String::from_utf8_lossy(request.body().unwrap()).into_owned();
+            let id = if body.contains("-100") { -100 } else { -1 };
+            chat(id).into_bytes()
+        })
+        .create_async()
+        .await;
+
+    let data_dir = tempfile::tempdir().unwrap();
+    let mut config = com_api_url(&server.url());
+    config.data_dir = data_dir.path().to_path_buf();
+    config.grupos.admin = -100;
+    config.grupos.shu = -1;
+    config.grupos.wei = -2;
+    config.grupos.wu = -3;
+    assert!(check_config(&config, None).await);
+
+    // Um grupo que o bot não enxerga
+    let _sumido = server
+        .mock("POST", "/bot123456:segredo/getChat")
+        .match_body(mockito::Matcher::Regex("-3".to_string()))
+        .with_status(400)
+        .with_body(r#"{"ok":false,"error_code":400,"description":"Bad Request: chat not found"}"#)
+        .create_async()
+        .await;
+    assert!(!check_config(&config, None).await);
+}
@@ -0,0 +1,42 @@
+use serde_json::Value;
+use std::path::Path;
+
+use yuanshao_bot::{replay, Config, Storage};
+
+#[tokio::test]
+async fn replaying_a_log_credits_the_team_and_records_what_was_sent() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(data_dir.path());
+    storage.add_inscrito("Zhao".to_string(), "zhao".to_string()).unwrap();
+    storage.vincular_inscrito("zhao", 5, "wei").unwrap();
+
+    let mut config = Config { data_dir: data_dir.path().to_path_buf(), ..Config::default() };
+    config.grupos.admin = -100;
+    config.grupos.wei = -2;
+    config.emojis.wei = "🐉".to_string();
+
+    // Um /start, um /add no grupo de administração e o /verificaremojis, que consulta o jogador com getChat
+    let scratch = data_dir.path().join("replay");
+    let log = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/replay/admin.jsonl");
+    let output = replay::replay_report(config, &log, Some(&scratch)).await.unwrap();
+
+    assert_eq!(output["mensagens_processadas"], 3);
+    assert_eq!(output["times"]["wei"]["naipes"][2]["pedra"], 1);
+    assert_eq!(Storage::new(&scratch).read_time_db("wei").unwrap().naipes[2].pedra, 1);
+    // O data_dir não é tocado
+    assert!(!data_dir.path().join("wei.json").exists());
+
+    let enviadas: Vec<(i64, &str)> = output["mensagens_enviadas"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m: &Value| (m["chat_id"].as_i64().unwrap(), m["texto"].as_str().unwrap()))
+        .collect();
+    assert_eq!(enviadas.len(), 4, "{:?}", enviadas);
+    assert_eq!(enviadas[0].0, 5);
+    assert!(enviadas[0].1.starts_with("Saudações, nobre guerreiro!"));
+    assert_eq!(enviadas[1], (-100, "Missão pedra do naipe 3 para o time WEI atualizada."));
+    assert_eq!(enviadas[2].0, -2);
+    assert!(enviadas[2].1.starts_with("Atenção, guerreiros de WEI!"));
+    assert_eq!(enviadas[3], (-100, "Inscritos sem o emoji do time no nome:\n\n- Zhao (@zhao), WEI 🐉: \"replay\"\n"));
+}
@@ -0,0 +1,95 @@
+use frankenstein::types::Message;
+use yuanshao_bot::domain::MissaoCumprida;
+use yuanshao_bot::parsing::parse_missao_descricao;
+use yuanshao_bot::resumo::{avaliar, parse_resumo, ResumoPartida};
+
+// Mensagens encaminhadas do bot do jogo, gravadas como chegaram ao bot
+fn fixture(nome: &str) -> Message {
+    let path = format!("{}/tests/fixtures/resumos/{}.json", env!("CARGO_MANIFEST_DIR"), nome);
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn resumo(nome: &str) -> ResumoPartida {
+    parse_resumo(fixture(nome).text.as_deref().unwrap()).unwrap()
+}
+
+const MISSOES: &str = "🪾 Naipe 03 🪾\n\n ● ⚒️ Como ferreiro, revele-se na 3 noite ou + (depois de duas forcas)(Pedra)\n\n\
+● 👻 Como fantasma, lidere a forca (Papel)\n\n\
+👾 Naipe 04 👾\n\n● 🐺🌬 Como Veloz, escape de uma forca (Pedra)\n\n\
+● 🥖 Como padeiro, morra e cause luto para a aldeia (Papel)\n\n\
+🏕 Naipe 10 🏕\n\n● ⛺️ Como sobrevivente ganhe a partida (Papel)\n\n\
+🕳 Naipe 18 🕳\n\n● 🐑 Como lobo trapaceiro, revele-se e sobreviva a uma forca (Pedra)";
+
+fn missao(naipe: usize, tipo: &str) -> String {
+    let missao = MissaoCumprida { naipe, tipo: tipo.to_string() };
+    parse_missao_descricao(MISSOES, &missao).unwrap()
+}
+
+#[test]
+fn extracts_players_roles_winners_and_deaths() {
+    let resumo = resumo("aldeia_vence");
+    assert_eq!(resumo.jogadores.len(), 8);
+
+    let zhao = resumo.jogador("Zhao Yun").unwrap();
+    assert_eq!(zhao.nome, "Zhao Yun 🐉");
+    assert_eq!(zhao.papel, "⚒️ Ferreiro");
+    assert!(zhao.vivo && zhao.venceu);
+
+    let mortos: Vec<&str> = resumo.mortos().map(|j| j.nome.as_str()).collect();
+    assert_eq!(mortos, ["Guan Yu 🐉", "Cao Cao 🦅", "Lu Bu", "Sun Quan 🐯"]);
+    assert_eq!(resumo.vencedores().count(), 6);
+    assert_eq!(resumo.jogador("Lu Bu").unwrap().papel, "🐺 Lobo Alfa");
+}
+
+#[test]
+fn accepts_summaries_without_dash_and_in_english() {
+    let lobos = resumo("lobos_vencem");
+    assert_eq!(lobos.jogadores.len(), 6);
+    let padeiro = lobos.jogador("Zhao Yun").unwrap();
+    assert_eq!(padeiro.papel, "🥖 Padeiro");
+    assert!(!padeiro.vivo && !padeiro.venceu);
+    assert_eq!(lobos.vencedores().count(), 2);
+
+    let ingles = resumo("em_ingles");
+    assert_eq!(ingles.jogadores.len(), 5);
+    assert_eq!(ingles.mortos().count(), 2);
+    assert_eq!(ingles.jogador("Lu Bu").unwrap().papel, "🔪 Serial Killer");
+}
+
+#[test]
+fn ordinary_messages_are_not_summaries() {
+    let comum = fixture("mensagem_comum");
+    assert!(comum.forward_origin.is_none());
+    assert!(parse_resumo(comum.text.as_deref().unwrap()).is_none());
+}
+
+#[test]
+fn finds_the_mission_description() {
+    assert_eq!(missao(3, "pedra"), "Como ferreiro, revele-se na 3 noite ou + (depois de duas forcas)");
+    assert_eq!(missao(4, "papel"), "Como padeiro, morra e cause luto para a aldeia");
+    assert!(parse_missao_descricao(MISSOES, &MissaoCumprida { naipe: 5, tipo: "pedra".to_string() }).is_none());
+}
+
+#[test]
+fn verdict_checks_role_outcome_and_team_emoji() {
+    let aldeia = resumo("aldeia_vence");
+    let ferreiro = avaliar(&aldeia, "Zhao Yun", Some(&missao(3, "pedra")), Some("🐉"));
+    assert!(ferreiro.provavelmente_valida(), "{:?}", ferreiro);
+
+    // Sun Quan era padeiro e morreu, mas jogou sem o emoji do time
+    let sem_emoji = avaliar(&aldeia, "Sun Quan", Some(&missao(4, "papel")), Some("🐉"));
+    assert_eq!(sem_emoji.problemas.len(), 1, "{:?}", sem_emoji);
+
+    // Liu Bei venceu, mas como vidente, e a missão pedia um sobrevivente
+    let papel_errado = avaliar(&aldeia, "Liu Bei", Some(&missao(10, "papel")), None);
+    assert!(!papel_errado.provavelmente_valida());
+
+    let lobos = resumo("lobos_vencem");
+    let padeiro = avaliar(&lobos, "Zhao Yun", Some(&missao(4, "papel")), None);
+    assert!(padeiro.provavelmente_valida(), "{:?}", padeiro);
+    let trapaceiro = avaliar(&lobos, "Xiahou Dun", Some(&missao(18, "pedra")), Some("🦅"));
+    assert!(trapaceiro.provavelmente_valida(), "{:?}", trapaceiro);
+
+    let ausente = avaliar(&lobos, "Cao Cao", None, None);
+    assert!(!ausente.provavelmente_valida());
+}
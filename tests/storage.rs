@@ -0,0 +1,93 @@
+use yuanshao_bot::domain::{MissaoCumprida, StatusEntrega};
+use yuanshao_bot::{Entrega, Storage, TIMES};
+
+#[test]
+fn times_start_with_ten_thousand_soldiers_and_import_skips_known_users() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(data_dir.path());
+    storage.inicializar_times();
+
+    for time in TIMES {
+        let db = storage.read_time_db(time).unwrap();
+        assert_eq!(db.soldados, 10000);
+        assert_eq!(db.naipes.len(), 22);
+    }
+
+    storage.add_inscrito("Liu Bei".to_string(), "liubei".to_string()).unwrap();
+    let (added, skipped) = storage
+        .import_inscritos(vec![
+            ("Liu Bei".to_string(), "@LiuBei".to_string()),
+            ("Cao Cao".to_string(), "caocao".to_string()),
+        ])
+        .unwrap();
+    assert_eq!(skipped, 1);
+    assert_eq!(added.len(), 1);
+    assert_eq!(added[0].id, 2);
+    assert_eq!(storage.read_inscritos().unwrap().len(), 2);
+}
+
+#[test]
+fn approval_is_saved_only_with_the_team_credit() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(data_dir.path());
+    let mut entrega = Entrega::from_provas("Zhao".to_string(), "zhao".to_string(), "shu".to_string(), &[]);
+    entrega.missao = Some(MissaoCumprida { naipe: 3, tipo: "pedra".to_string() });
+    let id = storage.append_entrega(entrega).unwrap();
+    let aprovar = |entrega: &mut Entrega| {
+        if entrega.status != StatusEntrega::Pendente {
+            return Err(yuanshao_bot::BotError::Recusa("já avaliada".to_string()));
+        }
+        entrega.status = StatusEntrega::Aprovada;
+        Ok(())
+    };
+
+    // Sem o banco do time, o crédito falha e a entrega continua pendente
+    assert!(storage.aprovar_entrega(id, aprovar).is_err());
+    assert_eq!(storage.buscar_entrega(id).unwrap().unwrap().status, StatusEntrega::Pendente);
+
+    storage.inicializar_times();
+    storage.aprovar_entrega(id, aprovar).unwrap();
+    assert_eq!(storage.buscar_entrega(id).unwrap().unwrap().status, StatusEntrega::Aprovada);
+    assert_eq!(storage.read_time_db("shu").unwrap().naipes[2].pedra, 1);
+    assert!(storage.aprovar_entrega(id, aprovar).is_err());
+    assert_eq!(storage.read_time_db("shu").unwrap().naipes[2].pedra, 1);
+}
+
+#[test]
+fn a_match_is_claimed_once_per_mission_even_at_the_same_time() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(data_dir.path());
+    let mut entrega = Entrega::from_provas("Zhao".to_string(), "zhao".to_string(), "wei".to_string(), &[]);
+    entrega.missao = Some(MissaoCumprida { naipe: 1, tipo: "papel".to_string() });
+    entrega.partidas = vec!["https://t.me/grupo/77".to_string()];
+
+    let registradas = std::thread::scope(|scope| {
+        let tentativas: Vec<_> = (0..8).map(|_| scope.spawn(|| storage.append_entrega(entrega.clone()))).collect();
+        tentativas.into_iter().map(|t| t.join().unwrap()).filter(Result::is_ok).count()
+    });
+    assert_eq!(registradas, 1);
+
+    // Outra missão pode usar a mesma partida
+    entrega.missao = Some(MissaoCumprida { naipe: 1, tipo: "pedra".to_string() });
+    assert!(storage.append_entrega(entrega).is_ok());
+}
+
+#[test]
+fn the_first_team_sticks_until_an_admin_changes_it() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(data_dir.path());
+    storage.add_inscrito("Zhao".to_string(), "Zhao".to_string()).unwrap();
+    let time = |storage: &Storage| storage.read_inscritos().unwrap()[0].time.clone();
+
+    assert!(storage.vincular_inscrito("zhao", 5, "shu").unwrap());
+    assert!(storage.vincular_inscrito("zhao", 5, "wei").unwrap());
+    assert_eq!(time(&storage).as_deref(), Some("shu"));
+
+    let (inscrito, anterior) = storage.corrigir_time("@ZHAO", "wei").unwrap().unwrap();
+    assert_eq!((inscrito.time.as_deref(), anterior.as_deref()), (Some("wei"), Some("shu")));
+    assert!(storage.vincular_inscrito("zhao", 5, "shu").unwrap());
+    assert_eq!(time(&storage).as_deref(), Some("wei"));
+    assert_eq!(storage.read_inscritos().unwrap()[0].user_id, Some(5));
+
+    assert!(storage.corrigir_time("@ninguem", "wu").unwrap().is_none());
+}